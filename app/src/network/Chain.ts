@@ -20,6 +20,7 @@ import type {
   PatchOffer,
   PostOffer,
   Profile,
+  ProfileOrder,
   TradeInfo,
 } from '~/types/components.interface'
 import { CosmosChain } from '~/network/cosmos/CosmosChain'
@@ -37,6 +38,8 @@ export interface Chain {
 
   fetchProfile(profile_addr?: Addr): Promise<Profile>
 
+  fetchProfiles(order: ProfileOrder, limit: number, startAfter?: Addr): Promise<Profile[]>
+
   fetchTokenBalance(denom: Denom): Promise<Coin>
 
   fetchOffer(offerId: string): Promise<OfferResponse>
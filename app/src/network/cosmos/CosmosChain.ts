@@ -20,6 +20,7 @@ import type {
   PatchOffer,
   PostOffer,
   Profile,
+  ProfileOrder,
   TradeInfo,
 } from '~/types/components.interface'
 import { denomToValue } from '~/utils/denom'
@@ -93,6 +94,29 @@ export class CosmosChain implements Chain {
     }
   }
 
+  async fetchProfiles(order: ProfileOrder, limit = 100, startAfter?: Addr) {
+    if (!this.cwClient) {
+      await this.init()
+    }
+    try {
+      const queryMsg = {
+        profiles: {
+          order,
+          limit,
+          start_after: startAfter,
+        },
+      }
+      const result = (await this.cwClient!.queryContractSmart(
+        this.hubInfo.hubConfig.profile_addr,
+        queryMsg
+      )) as Profile[]
+      console.log('Profiles result >> ', result)
+      return result
+    } catch (e) {
+      throw DefaultError.fromError(e)
+    }
+  }
+
   async fetchTokenBalance(denom: Denom) {
     if (this.cwClient instanceof SigningCosmWasmClient && this.signer) {
       try {
@@ -219,6 +243,7 @@ export class CosmosChain implements Chain {
           order: args.order,
           limit,
           last,
+          viewer: args.viewer,
         },
       }
       const response = (await this.cwClient!.queryContractSmart(
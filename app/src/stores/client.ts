@@ -5,7 +5,7 @@ import axios from 'axios'
 import { ListResult } from './ListResult'
 import { ChainClient, chainFactory } from '~/network/Chain'
 import type { ChainError } from '~/network/chain-error'
-import { WalletNotConnected } from '~/network/chain-error'
+import { DefaultError, WalletNotConnected } from '~/network/chain-error'
 import type {
   Addr,
   Arbitrator,
@@ -238,16 +238,23 @@ export const useClientStore = defineStore({
     async openTrade(offerResponse: OfferResponse, telegramHandle: string, amount: number) {
       this.loadingState = LoadingState.show('Opening trade...')
       try {
-        const profile_taker_encryption_key = this.getSecrets().publicKey
-        const taker_contact = await encryptData(offerResponse.profile.encryption_key!, telegramHandle)
-        const profile_taker_contact = await encryptData(profile_taker_encryption_key, telegramHandle)
+        // The trade is bound to the maker's current key, the listed profile may predate a rotation
+        const makerProfile = await this.client.fetchProfile(offerResponse.offer.owner)
+        if (!makerProfile.encryption_key) {
+          throw new DefaultError("The maker doesn't have an encryption key.")
+        }
+        const taker_contact = await encryptData(makerProfile.encryption_key, telegramHandle)
         const newTrade: NewTrade = {
           offer_id: offerResponse.offer.id,
           amount: `${Number(amount * CRYPTO_DECIMAL_PLACES).toFixed(0)}`,
           taker: `${this.userWallet.address}`,
-          profile_taker_contact,
           taker_contact,
-          profile_taker_encryption_key,
+        }
+        // The taker profile is only updated when it doesn't hold the current key yet
+        const profile_taker_encryption_key = this.getSecrets().publicKey
+        if (this.profile.encryption_key !== profile_taker_encryption_key) {
+          newTrade.profile_taker_contact = await encryptData(profile_taker_encryption_key, telegramHandle)
+          newTrade.profile_taker_encryption_key = profile_taker_encryption_key
         }
         const trade_id = await this.client.openTrade(newTrade)
         const tradeInfo = await this.fetchTradeDetail(trade_id)
@@ -44,8 +44,47 @@ export interface Profile {
   last_trade: number
   contact?: string
   encryption_key?: string
+  encryption_key_version: number
   active_offers_count: number
   active_trades_count: number
+  canceled_trades_count: number
+  expired_trades_count: number
+  disputes_opened_count: number
+  disputes_won_count: number
+  disputes_lost_count: number
+  traded_volume: TradedVolume[]
+  usd_traded_volume: string
+  reviews_count: number
+  rating_sum: number
+  average_rating: string
+  suspension?: Suspension
+  badges: Badge[]
+}
+
+export interface TradedVolume {
+  denom: Denom
+  amount: string
+}
+
+export interface Suspension {
+  reason: string
+  moderator: Addr
+  suspended_at: number
+  expires_at?: number
+}
+
+export interface Badge {
+  name: string
+  attester: Addr
+  commitment?: string
+  attested_at: number
+  expires_at: number
+}
+
+export enum ProfileOrder {
+  released_trades = 'released_trades',
+  last_trade = 'last_trade',
+  created_at = 'created_at',
 }
 
 export interface GetOffer {
@@ -60,8 +99,26 @@ export interface GetOffer {
   description?: string
   fiat_currency: FiatCurrency
   timestamp: number
+  fiat_limits?: FiatLimits
+  taker_requirements?: TakerRequirements
+}
+
+// Limits in whole units of the offer's fiat, they replace min_amount and max_amount when set
+export interface FiatLimits {
+  min: string
+  max: string
+}
+
+export interface TakerRequirements {
+  min_released_trades?: number
+  min_profile_age?: number
+  max_dispute_loss_ratio?: string
+  address_filter?: AddressFilter
+  required_badges: string[]
 }
 
+export type AddressFilter = { allowlist: Addr[] } | { blocklist: Addr[] }
+
 export interface PatchOffer {
   id: number
   state: OfferState
@@ -70,19 +127,23 @@ export interface PatchOffer {
   max_amount: string
   owner_contact?: string
   owner_encryption_key?: string
+  fiat_limits?: FiatLimits
   description?: string
+  taker_requirements?: TakerRequirements
 }
 
 export interface PostOffer {
-  owner_contact: string
-  owner_encryption_key: string
+  owner_contact?: string
+  owner_encryption_key?: string
   rate: string
   offer_type: OfferType
   denom: Denom
   fiat_currency: FiatCurrency
   min_amount: string
   max_amount: string
+  fiat_limits?: FiatLimits
   description?: string
+  taker_requirements?: TakerRequirements
 }
 
 export enum FiatCurrency {
@@ -112,18 +173,25 @@ export interface FetchOffersArgs {
   offerType: OfferType
   denom: Denom
   order: OfferOrder
+  viewer?: Addr
 }
 export enum OfferOrder {
   trades_count = 'trades_count',
   price_rate = 'price_rate',
+  rating = 'rating',
+  volume = 'volume',
+  dispute_loss_ratio = 'dispute_loss_ratio',
 }
 
 export interface NewTrade {
   offer_id: number
   amount: string
+  // In whole units of the offer's fiat, when set the amount is computed from it
+  fiat_amount?: string
   taker: string
-  profile_taker_contact: string
-  profile_taker_encryption_key: string
+  profile_taker_contact?: string
+  profile_taker_encryption_key?: string
+  // Encrypted to the maker's current key, the profile contact can't stand in for it
   taker_contact: string
 }
 
@@ -149,6 +217,8 @@ export interface Trade {
   amount: string
   denom: Denom
   denom_fiat_price: number
+  usd_amount: string
+  config_version: number
   state: TradeState
   state_history: TradeStateItem[]
   fiat: FiatCurrency
@@ -200,6 +270,15 @@ export interface HubConfig {
   trade_expiration_timer: number
   trade_limit_min: number
   trade_limit_max: number
+  config_timelock: number
+  trade_price_twap_window: number
+  fiat_trade_limits: [FiatCurrency, TradeLimits][]
+  denom_trade_limits: [Denom, TradeLimits][]
+}
+
+export interface TradeLimits {
+  min: number
+  max: number
 }
 
 export type Addr = string
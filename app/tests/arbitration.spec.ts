@@ -4,7 +4,7 @@ import { jest } from '@jest/globals'
 import offers from './fixtures/offers.json'
 import { setupProtocol, sleep } from './utils'
 import type { TestCosmosChain } from './network/TestCosmosChain'
-import { encryptDataMocked } from './helper'
+import { encryptDataMocked, makerEncryptionKey } from './helper'
 import takerSecrets from './fixtures/taker_secrets.json'
 import makerSecrets from './fixtures/maker_secrets.json'
 import adminSecrets from './fixtures/admin_secrets.json'
@@ -70,7 +70,7 @@ describe('arbitration tests', () => {
   it('should settle dispute for taker', async () => {
     const profile_taker_contact = await encryptDataMocked(takerSecrets.publicKey, takerContact)
     const profile_taker_encryption_key = takerSecrets.publicKey
-    const taker_contact = await encryptDataMocked(offerResponse[0].profile.encryption_key!, takerContact)
+    const taker_contact = await encryptDataMocked(await makerEncryptionKey(takerClient, offer.owner), takerContact)
     // Create a Trade and set it to `fiat_deposited` state.
     const tradeId = await takerClient.openTrade({
       amount: offer.min_amount,
@@ -111,7 +111,7 @@ describe('arbitration tests', () => {
   it('should settle dispute for maker', async () => {
     const profile_taker_contact = await encryptDataMocked(takerSecrets.publicKey, takerContact)
     const taker_encrypt_pk = takerSecrets.publicKey
-    const taker_contact = await encryptDataMocked(offerResponse[0].profile.encryption_key!, takerContact)
+    const taker_contact = await encryptDataMocked(await makerEncryptionKey(takerClient, offer.owner), takerContact)
 
     const tradeId = await takerClient.openTrade({
       amount: offer.min_amount,
@@ -146,7 +146,7 @@ describe('arbitration tests', () => {
   it('should not be able to open a dispute prematurely', async () => {
     const profile_taker_contact = await encryptDataMocked(takerSecrets.publicKey, takerContact)
     const taker_encrypt_pk = takerSecrets.publicKey
-    const taker_contact = await encryptDataMocked(offerResponse[0].profile.encryption_key!, takerContact)
+    const taker_contact = await encryptDataMocked(await makerEncryptionKey(takerClient, offer.owner), takerContact)
 
     const tradeId = await takerClient.openTrade({
       amount: offer.min_amount,
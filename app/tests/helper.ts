@@ -1,6 +1,12 @@
 import makerSecrets from './fixtures/maker_secrets.json'
 import takerSecrets from './fixtures/taker_secrets.json'
 import adminSecrets from './fixtures/admin_secrets.json'
+import type { Chain } from '~/network/Chain'
+
+// Trades are bound to the maker's current key, the one listed with an offer may have been rotated
+export async function makerEncryptionKey(client: Chain, maker: string): Promise<string> {
+  return (await client.fetchProfile(maker)).encryption_key!
+}
 
 export async function encryptDataMocked(key: string, data: string): Promise<string> {
   let encryptedData = ''
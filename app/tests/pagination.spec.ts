@@ -5,7 +5,7 @@ import { setupProtocol } from './utils'
 import type { TestCosmosChain } from './network/TestCosmosChain'
 import makerSecrets from './fixtures/maker_secrets.json'
 import takerSecrets from './fixtures/taker_secrets.json'
-import { encryptDataMocked } from './helper'
+import { encryptDataMocked, makerEncryptionKey } from './helper'
 import adminSecrets from './fixtures/admin_secrets.json'
 import type { PatchOffer } from '~/types/components.interface'
 import { FiatCurrency, OfferOrder, OfferState, OfferType, TradeState } from '~/types/components.interface'
@@ -97,7 +97,10 @@ describe('trades pagination', () => {
       const offers = await takerClient.fetchOffers(args, 1)
       const offerResponse = offers[0]
       const profileTakerContact = await encryptDataMocked(takerSecrets.publicKey, 'taker001')
-      const takerContact = await encryptDataMocked(offerResponse.profile.encryption_key!, 'taker001')
+      const takerContact = await encryptDataMocked(
+        await makerEncryptionKey(takerClient, offerResponse.offer.owner),
+        'taker001'
+      )
       for (let i = 0; i < limit - trades.length; i++) {
         await takerClient.openTrade({
           amount: offerResponse.offer.min_amount,
@@ -9,7 +9,7 @@ import adminSecrets from './fixtures/admin_secrets.json'
 import { createHubUpdateConfigMsg, getOrCreateOffer, setupProtocol } from './utils'
 import type { TestCosmosChain } from './network/TestCosmosChain'
 import prices from './fixtures/update_prices.json'
-import { decryptDataMocked, encryptDataMocked } from './helper'
+import { decryptDataMocked, encryptDataMocked, makerEncryptionKey } from './helper'
 import { DefaultError } from '~/network/chain-error'
 import type { GetOffer, OfferResponse, PostOffer, TradeInfo } from '~/types/components.interface'
 import { FiatCurrency, OfferState, OfferType, TradeState } from '~/types/components.interface'
@@ -74,7 +74,7 @@ describe('trade lifecycle happy path', () => {
   // Create Trade
   it('taker should create a trade', async () => {
     const offer = offerResponse.offer
-    const taker_contact = await encryptDataMocked(offerResponse.profile.encryption_key!, takerContact)
+    const taker_contact = await encryptDataMocked(await makerEncryptionKey(takerClient, offer.owner), takerContact)
     expect(offer).toHaveProperty('id')
     const profile_taker_contact = await encryptDataMocked(takerSecrets.publicKey, takerContact)
     const profile_taker_encrypt_key = takerSecrets.publicKey
@@ -165,7 +165,7 @@ describe('trade invalid state changes', () => {
 
     const profile_taker_contact = await encryptDataMocked(takerSecrets.publicKey, takerContact)
     const taker_encrypt_pk = takerSecrets.publicKey
-    const taker_contact = await encryptDataMocked(offerResponse.profile.encryption_key!, takerContact)
+    const taker_contact = await encryptDataMocked(await makerEncryptionKey(takerClient, offer.owner), takerContact)
     tradeId = await takerClient.openTrade({
       amount: tradeAmount,
       offer_id: offerResponse.offer.id,
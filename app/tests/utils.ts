@@ -32,6 +32,10 @@ export function createHubUpdateConfigMsg(offerAddr: string, tradeAddr: string, p
       warchest_fee_pct: '0.005', // 0.5%
       trade_expiration_timer: TRADE_EXPIRATION_TIMER,
       trade_dispute_timer: TRADE_DISPUTE_TIMER,
+      config_timelock: 0, // in seconds, updates are applied right away
+      trade_price_twap_window: 0, // in seconds, trades are priced on spot
+      fiat_trade_limits: [], // overrides of the trade limits by fiat
+      denom_trade_limits: [], // overrides of the trade limits by denom
     },
   }
 }
@@ -1,21 +1,42 @@
-use cosmwasm_std::{entry_point, Addr, Binary, Decimal, Deps, StdResult, Storage, Uint64};
+use cosmwasm_std::{
+    entry_point, Addr, Binary, Decimal, Deps, Event, Order, StdError, StdResult, Storage, Uint128,
+    Uint64,
+};
 use cosmwasm_std::{to_binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, WasmMsg};
 use cw2::{get_contract_version, set_contract_version};
 use localmoney_protocol::constants::{
-    MAX_PLATFORM_FEE, MAX_TRADE_DISPUTE_TIMER, MAX_TRADE_EXPIRATION_TIMER,
+    BADGES_LIMIT, MAX_CONFIG_TIMELOCK, MAX_PLATFORM_FEE, MAX_TRADE_DISPUTE_TIMER,
+    MAX_TRADE_EXPIRATION_TIMER,
 };
 
-use crate::state::{ADMIN, CONFIG};
+use crate::state::{
+    ADMIN, ATTESTERS, CONFIG, CONFIG_HISTORY, HUB_STATE_VERSION, MODERATOR, PAUSE_STATUS,
+    PENDING_ADMIN, PENDING_CONFIG, REPUTATION_TIERS,
+};
+use cw20::Denom;
+use localmoney_protocol::currencies::FiatCurrency;
+use localmoney_protocol::denom_utils::denom_to_string;
 use localmoney_protocol::errors::ContractError;
 use localmoney_protocol::errors::ContractError::Unauthorized;
-use localmoney_protocol::guards::assert_migration_parameters;
+use localmoney_protocol::guards::{
+    assert_badge_name_valid, assert_migration_parameters, assert_min_g_max,
+};
 use localmoney_protocol::hub::{
-    Admin, ExecuteMsg, HubConfig, InstantiateMsg, MigrateMsg, QueryMsg,
+    Admin, Attester, ExecuteMsg, HubConfig, HubState, InstantiateMsg, MigrateMsg, PauseTarget,
+    PendingConfig, QueryMsg, ReputationTier, TradeLimits, VersionedConfig,
 };
 use localmoney_protocol::offer::ExecuteMsg::RegisterHub as OfferRegisterHub;
+use localmoney_protocol::offer::ExecuteMsg::UpdateHubConfig as OfferUpdateHubConfig;
+use localmoney_protocol::offer::ExecuteMsg::UpdateHubState as OfferUpdateHubState;
 use localmoney_protocol::price::ExecuteMsg::RegisterHub as PriceRegisterHub;
+use localmoney_protocol::price::ExecuteMsg::UpdateHubConfig as PriceUpdateHubConfig;
+use localmoney_protocol::price::ExecuteMsg::UpdateHubState as PriceUpdateHubState;
 use localmoney_protocol::profile::ExecuteMsg::RegisterHub as ProfileRegisterHub;
+use localmoney_protocol::profile::ExecuteMsg::UpdateHubConfig as ProfileUpdateHubConfig;
+use localmoney_protocol::profile::ExecuteMsg::UpdateHubState as ProfileUpdateHubState;
 use localmoney_protocol::trade::ExecuteMsg::RegisterHub as TradeRegisterHub;
+use localmoney_protocol::trade::ExecuteMsg::UpdateHubConfig as TradeUpdateHubConfig;
+use localmoney_protocol::trade::ExecuteMsg::UpdateHubState as TradeUpdateHubState;
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -43,31 +64,148 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig(config) => update_config(deps, info, config),
+        ExecuteMsg::UpdateConfig(config) => update_config(deps, env, info, config),
+        ExecuteMsg::ExecuteConfigUpdate {} => execute_config_update(deps, env, info),
+        ExecuteMsg::CancelConfigUpdate {} => cancel_config_update(deps, info),
         ExecuteMsg::UpdateAdmin { admin_addr } => update_admin(deps, info, admin_addr),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, info),
+        ExecuteMsg::UpdatePause { target, paused } => update_pause(deps, info, target, paused),
+        ExecuteMsg::UpdateReputationTiers { tiers } => update_reputation_tiers(deps, info, tiers),
+        ExecuteMsg::UpdateModerator { moderator } => update_moderator(deps, info, moderator),
+        ExecuteMsg::UpdateAttester { attester, badges } => {
+            update_attester(deps, info, attester, badges)
+        }
     }
 }
 
 fn update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     config: HubConfig,
 ) -> Result<Response, ContractError> {
-    let admin = ADMIN.load(deps.storage).unwrap();
-    if !info.sender.eq(&admin.addr) {
-        return Err(Unauthorized {
-            owner: admin.addr.clone(),
-            caller: info.sender.clone(),
+    assert_admin(deps.storage, &info.sender)?;
+    validate_config(&config)?;
+
+    // The first config is applied right away, otherwise the protocol couldn't be bootstrapped.
+    let current_config = match CONFIG.may_load(deps.storage).unwrap() {
+        Some(current_config) if current_config.config_timelock > 0 => current_config,
+        current_config => {
+            let diff = current_config
+                .map(|current_config| current_config.diff(&config))
+                .unwrap_or_default();
+            return Ok(apply_config(deps, env, info, config)?
+                .add_event(config_diff_event("config_updated", diff)));
+        }
+    };
+
+    // Queuing over a pending update would drop it without a trace, it has to be canceled first.
+    if let Some(pending_config) = PENDING_CONFIG.may_load(deps.storage).unwrap() {
+        return Err(ContractError::ConfigUpdatePending {
+            executable_at: pending_config.executable_at,
+        });
+    }
+
+    let proposed_at = env.block.time.seconds();
+    let executable_at = proposed_at + current_config.config_timelock;
+    let diff = current_config.diff(&config);
+    PENDING_CONFIG
+        .save(
+            deps.storage,
+            &PendingConfig {
+                config,
+                proposed_at,
+                executable_at,
+            },
+        )
+        .unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "queue_config_update")
+        .add_attribute("executable_at", executable_at.to_string())
+        .add_event(
+            config_diff_event("config_update_queued", diff)
+                .add_attribute("executable_at", executable_at.to_string()),
+        );
+    Ok(res)
+}
+
+fn execute_config_update(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let pending_config = match PENDING_CONFIG.may_load(deps.storage).unwrap() {
+        Some(pending_config) => pending_config,
+        None => return Err(ContractError::NoPendingConfig {}),
+    };
+    if env.block.time.seconds() < pending_config.executable_at {
+        return Err(ContractError::ConfigTimelocked {
+            executable_at: pending_config.executable_at,
         });
     }
+    PENDING_CONFIG.remove(deps.storage);
 
+    let current_config = CONFIG.load(deps.storage).unwrap();
+    let diff = current_config.diff(&pending_config.config);
+    Ok(apply_config(deps, env, info, pending_config.config)?
+        .add_event(config_diff_event("config_updated", diff)))
+}
+
+fn cancel_config_update(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let pending_config = match PENDING_CONFIG.may_load(deps.storage).unwrap() {
+        Some(pending_config) => pending_config,
+        None => return Err(ContractError::NoPendingConfig {}),
+    };
+    PENDING_CONFIG.remove(deps.storage);
+
+    let res = Response::new()
+        .add_attribute("action", "cancel_config_update")
+        .add_event(
+            Event::new("config_update_canceled")
+                .add_attribute("proposed_at", pending_config.proposed_at.to_string()),
+        );
+    Ok(res)
+}
+
+// Each changed field is emitted as `field: "old -> new"`, so config changes can be monitored.
+fn config_diff_event(event_type: &str, diff: Vec<(String, String)>) -> Event {
+    Event::new(event_type)
+        .add_attribute("changed_fields", diff.len().to_string())
+        .add_attributes(diff)
+}
+
+fn apply_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: HubConfig,
+) -> Result<Response, ContractError> {
     save_config(deps.storage, &config)?;
 
+    // Every accepted config is stored under a new version, so past configs can be audited.
+    let version = query_versioned_config(deps.as_ref())
+        .map(|versioned_config| versioned_config.version + 1)
+        .unwrap_or(1);
+    let versioned_config = VersionedConfig {
+        version,
+        height: env.block.height,
+        time: env.block.time.seconds(),
+        config: config.clone(),
+    };
+    CONFIG_HISTORY
+        .save(deps.storage, version, &versioned_config)
+        .unwrap();
+
     let offer_register_hub = SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.offer_addr.to_string(),
         msg: to_binary(&OfferRegisterHub {}).unwrap(),
@@ -92,12 +230,43 @@ fn update_config(
         funds: info.funds.clone(),
     }));
 
+    // Push the accepted config, so the contracts don't need to query the Hub on every call.
+    let update_hub_config_msgs = vec![
+        (
+            config.offer_addr.to_string(),
+            to_binary(&OfferUpdateHubConfig(Box::new(versioned_config.clone()))).unwrap(),
+        ),
+        (
+            config.price_addr.to_string(),
+            to_binary(&PriceUpdateHubConfig(Box::new(versioned_config.clone()))).unwrap(),
+        ),
+        (
+            config.profile_addr.to_string(),
+            to_binary(&ProfileUpdateHubConfig(Box::new(versioned_config.clone()))).unwrap(),
+        ),
+        (
+            config.trade_addr.to_string(),
+            to_binary(&TradeUpdateHubConfig(Box::new(versioned_config))).unwrap(),
+        ),
+    ]
+    .into_iter()
+    .map(|(contract_addr, msg)| {
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds: vec![],
+        }))
+    });
+
     let res = Response::new()
         .add_attribute("action", "update_config")
+        .add_attribute("config_version", version.to_string())
         .add_submessage(offer_register_hub)
         .add_submessage(price_register_hub)
         .add_submessage(profile_register_hub)
         .add_submessage(trade_register_hub)
+        .add_submessages(update_hub_config_msgs)
+        .add_submessages(update_hub_state_msgs(deps.storage))
         .add_attribute("local_market_addr", config.local_market_addr)
         .add_attribute("offer_addr", config.offer_addr)
         .add_attribute("price_addr", config.price_addr)
@@ -106,7 +275,72 @@ fn update_config(
     Ok(res)
 }
 
+// Push the state to the registered contracts, under a new version, so they don't need
+// to query the Hub on every call. Nothing is pushed before the first config.
+fn update_hub_state_msgs(storage: &mut dyn Storage) -> Vec<SubMsg> {
+    let config = match CONFIG.may_load(storage).unwrap() {
+        Some(config) => config,
+        None => return vec![],
+    };
+    let version = HUB_STATE_VERSION
+        .may_load(storage)
+        .unwrap()
+        .unwrap_or_default()
+        + 1;
+    HUB_STATE_VERSION.save(storage, &version).unwrap();
+    let state = HubState {
+        version,
+        admin: ADMIN.load(storage).unwrap(),
+        pause_status: PAUSE_STATUS.may_load(storage).unwrap().unwrap_or_default(),
+        reputation_tiers: REPUTATION_TIERS
+            .may_load(storage)
+            .unwrap()
+            .unwrap_or_default(),
+        moderator: MODERATOR.may_load(storage).unwrap(),
+        attesters: ATTESTERS
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(addr, badges)| Attester { addr, badges }))
+            .collect::<StdResult<_>>()
+            .unwrap(),
+    };
+
+    vec![
+        (
+            config.offer_addr.to_string(),
+            to_binary(&OfferUpdateHubState(Box::new(state.clone()))).unwrap(),
+        ),
+        (
+            config.price_addr.to_string(),
+            to_binary(&PriceUpdateHubState(Box::new(state.clone()))).unwrap(),
+        ),
+        (
+            config.profile_addr.to_string(),
+            to_binary(&ProfileUpdateHubState(Box::new(state.clone()))).unwrap(),
+        ),
+        (
+            config.trade_addr.to_string(),
+            to_binary(&TradeUpdateHubState(Box::new(state))).unwrap(),
+        ),
+    ]
+    .into_iter()
+    .map(|(contract_addr, msg)| {
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds: vec![],
+        }))
+    })
+    .collect()
+}
+
 fn save_config(storage: &mut dyn Storage, config: &HubConfig) -> Result<(), ContractError> {
+    validate_config(config)?;
+    CONFIG.save(storage, config).unwrap();
+
+    Ok(())
+}
+
+fn validate_config(config: &HubConfig) -> Result<(), ContractError> {
     // The total_platform_fee is the sum of the fees charged in the release_escrow
     // and it cannot be greater than the MAX_PLATFORM_FEE (10%)
     let total_platform_fee = config.chain_fee_pct + config.burn_fee_pct + config.warchest_fee_pct;
@@ -128,7 +362,41 @@ fn save_config(storage: &mut dyn Storage, config: &HubConfig) -> Result<(), Cont
         MAX_TRADE_DISPUTE_TIMER,
     )?;
 
-    CONFIG.save(storage, config).unwrap();
+    // A timelock of 0 applies config updates immediately
+    if config.config_timelock > MAX_CONFIG_TIMELOCK {
+        return Err(ContractError::InvalidParameter {
+            parameter: "config_timelock".to_string(),
+            message: Some(format!(
+                "This value should be smaller than {0}.",
+                MAX_CONFIG_TIMELOCK
+            )),
+        });
+    }
+
+    // Each override, and each fiat and denom overrides merged, must leave a valid range
+    let overrides = config
+        .fiat_trade_limits
+        .iter()
+        .map(|(_, limits)| limits)
+        .chain(config.denom_trade_limits.iter().map(|(_, limits)| limits));
+    for limits in overrides {
+        assert_min_g_max(Uint128::from(limits.min), Uint128::from(limits.max))?;
+    }
+    for (fiat, _) in config.fiat_trade_limits.iter() {
+        for (denom, _) in config.denom_trade_limits.iter() {
+            let limits = TradeLimits::effective(config, fiat, denom);
+            if limits.min > limits.max {
+                return Err(ContractError::InvalidParameter {
+                    parameter: "trade_limits".to_string(),
+                    message: Some(format!(
+                        "The {} and {} limits don't overlap.",
+                        fiat,
+                        denom_to_string(denom)
+                    )),
+                });
+            }
+        }
+    }
 
     Ok(())
 }
@@ -150,22 +418,167 @@ fn update_admin(
     info: MessageInfo,
     new_admin: Addr,
 ) -> Result<Response, ContractError> {
-    let mut admin = ADMIN.load(deps.storage).unwrap();
-    if !info.sender.eq(&admin.addr) {
+    let admin = assert_admin(deps.storage, &info.sender)?;
+
+    // The new admin has to accept the transfer, so a wrong address can't lock the hub.
+    PENDING_ADMIN
+        .save(
+            deps.storage,
+            &Admin {
+                addr: new_admin.clone(),
+            },
+        )
+        .unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "propose_admin")
+        .add_attribute("admin", admin.addr)
+        .add_attribute("pending_admin", new_admin);
+    Ok(res)
+}
+
+fn accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = match PENDING_ADMIN.may_load(deps.storage).unwrap() {
+        Some(pending_admin) => pending_admin,
+        None => return Err(ContractError::NoPendingAdmin {}),
+    };
+    if !info.sender.eq(&pending_admin.addr) {
         return Err(Unauthorized {
-            owner: admin.addr.clone(),
+            owner: pending_admin.addr.clone(),
             caller: info.sender.clone(),
         });
     }
 
-    let old_admin = admin.addr.clone();
-    admin.addr = new_admin.clone();
-    ADMIN.save(deps.storage, &admin).unwrap();
+    let old_admin = ADMIN.load(deps.storage).unwrap().addr;
+    ADMIN.save(deps.storage, &pending_admin).unwrap();
+    PENDING_ADMIN.remove(deps.storage);
 
     let res = Response::new()
+        .add_submessages(update_hub_state_msgs(deps.storage))
         .add_attribute("action", "update_admin")
         .add_attribute("old_admin", old_admin)
-        .add_attribute("new_admin", new_admin);
+        .add_attribute("new_admin", pending_admin.addr);
+    Ok(res)
+}
+
+fn assert_admin(storage: &dyn Storage, sender: &Addr) -> Result<Admin, ContractError> {
+    let admin = ADMIN.load(storage).unwrap();
+    if !sender.eq(&admin.addr) {
+        return Err(Unauthorized {
+            owner: admin.addr.clone(),
+            caller: sender.clone(),
+        });
+    }
+    Ok(admin)
+}
+
+fn update_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    target: PauseTarget,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let mut pause_status = PAUSE_STATUS
+        .may_load(deps.storage)
+        .unwrap()
+        .unwrap_or_default();
+    match target.clone() {
+        PauseTarget::Global => pause_status.global = paused,
+        PauseTarget::Contract(contract) => {
+            pause_status.contracts.retain(|c| c.ne(&contract));
+            if paused {
+                pause_status.contracts.push(contract);
+            }
+        }
+        PauseTarget::Operation(operation) => {
+            pause_status.operations.retain(|o| o.ne(&operation));
+            if paused {
+                pause_status.operations.push(operation);
+            }
+        }
+    }
+    PAUSE_STATUS.save(deps.storage, &pause_status).unwrap();
+
+    let res = Response::new()
+        .add_submessages(update_hub_state_msgs(deps.storage))
+        .add_attribute("action", "update_pause")
+        .add_attribute("target", target.to_string())
+        .add_attribute("paused", paused.to_string());
+    Ok(res)
+}
+
+fn update_reputation_tiers(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut tiers: Vec<ReputationTier>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    tiers.sort_by_key(|tier| tier.min_released_trades);
+    REPUTATION_TIERS.save(deps.storage, &tiers).unwrap();
+
+    let res = Response::new()
+        .add_submessages(update_hub_state_msgs(deps.storage))
+        .add_attribute("action", "update_reputation_tiers")
+        .add_attribute("tiers_count", tiers.len().to_string());
+    Ok(res)
+}
+
+// The moderator can suspend profiles, passing None removes it
+fn update_moderator(
+    deps: DepsMut,
+    info: MessageInfo,
+    moderator: Option<Addr>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    match moderator.clone() {
+        Some(moderator) => MODERATOR.save(deps.storage, &moderator).unwrap(),
+        None => MODERATOR.remove(deps.storage),
+    }
+
+    let res = Response::new()
+        .add_submessages(update_hub_state_msgs(deps.storage))
+        .add_attribute("action", "update_moderator")
+        .add_attribute(
+            "moderator",
+            moderator.map(|m| m.to_string()).unwrap_or_default(),
+        );
+    Ok(res)
+}
+
+// Passing None removes the attester, the badges it already attested stay until they expire
+fn update_attester(
+    deps: DepsMut,
+    info: MessageInfo,
+    attester: Addr,
+    badges: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    match badges.clone() {
+        Some(badges) => {
+            if badges.len() > BADGES_LIMIT {
+                return Err(ContractError::InvalidParameter {
+                    parameter: "badges".to_string(),
+                    message: Some(format!(
+                        "An attester can not have more than {} badges.",
+                        BADGES_LIMIT
+                    )),
+                });
+            }
+            for badge in badges.iter() {
+                assert_badge_name_valid(badge)?;
+            }
+            ATTESTERS.save(deps.storage, &attester, &badges).unwrap()
+        }
+        None => ATTESTERS.remove(deps.storage, &attester),
+    }
+
+    let res = Response::new()
+        .add_submessages(update_hub_state_msgs(deps.storage))
+        .add_attribute("action", "update_attester")
+        .add_attribute("attester", attester.to_string())
+        .add_attribute("badges", badges.unwrap_or_default().join(","));
     Ok(res)
 }
 
@@ -173,7 +586,79 @@ fn update_admin(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&CONFIG.load(deps.storage).unwrap()),
+        QueryMsg::VersionedConfig {} => to_binary(&query_versioned_config(deps)?),
+        QueryMsg::ConfigAt { version, height } => {
+            to_binary(&query_config_at(deps, version, height)?)
+        }
         QueryMsg::Admin {} => to_binary(&ADMIN.load(deps.storage).unwrap()),
+        QueryMsg::PendingConfig {} => to_binary(&PENDING_CONFIG.may_load(deps.storage).unwrap()),
+        QueryMsg::PendingAdmin {} => to_binary(&PENDING_ADMIN.may_load(deps.storage).unwrap()),
+        QueryMsg::PauseStatus {} => to_binary(
+            &PAUSE_STATUS
+                .may_load(deps.storage)
+                .unwrap()
+                .unwrap_or_default(),
+        ),
+        QueryMsg::TradeLimits { fiat, denom } => to_binary(&query_trade_limits(deps, fiat, denom)?),
+        QueryMsg::Moderator {} => to_binary(&MODERATOR.may_load(deps.storage).unwrap()),
+        QueryMsg::Attester { addr } => to_binary(
+            &ATTESTERS
+                .may_load(deps.storage, &addr)?
+                .map(|badges| Attester { addr, badges }),
+        ),
+        QueryMsg::Attesters {} => to_binary(&query_attesters(deps)?),
+        QueryMsg::ReputationTiers {} => to_binary(
+            &REPUTATION_TIERS
+                .may_load(deps.storage)
+                .unwrap()
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn query_attesters(deps: Deps) -> StdResult<Vec<Attester>> {
+    ATTESTERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(addr, badges)| Attester { addr, badges }))
+        .collect()
+}
+
+fn query_trade_limits(deps: Deps, fiat: FiatCurrency, denom: Denom) -> StdResult<TradeLimits> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(TradeLimits::effective(&config, &fiat, &denom))
+}
+
+fn query_versioned_config(deps: Deps) -> StdResult<VersionedConfig> {
+    let latest = CONFIG_HISTORY
+        .range(deps.storage, None, None, Order::Descending)
+        .next();
+    match latest {
+        Some(item) => item.map(|(_, versioned_config)| versioned_config),
+        // Configs saved before the history existed are reported as version 0
+        None => Ok(VersionedConfig {
+            version: 0,
+            height: 0,
+            time: 0,
+            config: CONFIG.load(deps.storage)?,
+        }),
+    }
+}
+
+fn query_config_at(
+    deps: Deps,
+    version: Option<u64>,
+    height: Option<u64>,
+) -> StdResult<VersionedConfig> {
+    match (version, height) {
+        (Some(version), None) => CONFIG_HISTORY.load(deps.storage, version),
+        (None, Some(height)) => CONFIG_HISTORY
+            .range(deps.storage, None, None, Order::Descending)
+            .flat_map(|item| item.map(|(_, versioned_config)| versioned_config))
+            .find(|versioned_config| versioned_config.height <= height)
+            .ok_or_else(|| StdError::not_found("VersionedConfig")),
+        _ => Err(StdError::generic_err(
+            "Either version or height must be provided.",
+        )),
     }
 }
 
@@ -1,6 +1,19 @@
-use cw_storage_plus::Item;
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Item, Map};
 
-use localmoney_protocol::hub::{Admin, HubConfig};
+use localmoney_protocol::hub::{
+    Admin, HubConfig, PauseStatus, PendingConfig, ReputationTier, VersionedConfig,
+};
 
 pub const CONFIG: Item<HubConfig> = Item::new("config");
+pub const CONFIG_HISTORY: Map<u64, VersionedConfig> = Map::new("config_history");
 pub const ADMIN: Item<Admin> = Item::new("admin");
+pub const PENDING_CONFIG: Item<PendingConfig> = Item::new("pending_config");
+pub const PENDING_ADMIN: Item<Admin> = Item::new("pending_admin");
+pub const PAUSE_STATUS: Item<PauseStatus> = Item::new("pause_status");
+pub const REPUTATION_TIERS: Item<Vec<ReputationTier>> = Item::new("reputation_tiers");
+pub const MODERATOR: Item<Addr> = Item::new("moderator");
+// Badges each attester is allowed to attest
+pub const ATTESTERS: Map<&Addr, Vec<String>> = Map::new("attesters");
+// Increased on every push of the state to the registered contracts
+pub const HUB_STATE_VERSION: Item<u64> = Item::new("hub_state_version");
@@ -0,0 +1,329 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{from_binary, Addr, Decimal, MemoryStorage, OwnedDeps};
+use cw20::Denom;
+use localmoney_protocol::currencies::FiatCurrency;
+use localmoney_protocol::errors::ContractError;
+use localmoney_protocol::hub::{
+    Admin, ExecuteMsg, HubConfig, InstantiateMsg, PauseStatus, PauseTarget, PendingConfig,
+    ProtocolContract, ProtocolOperation, QueryMsg, TradeLimits, VersionedConfig,
+};
+
+type MockDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+fn setup() -> MockDeps {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin_addr: Addr::unchecked("admin"),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    deps
+}
+
+fn config() -> HubConfig {
+    HubConfig {
+        offer_addr: Addr::unchecked("offer"),
+        trade_addr: Addr::unchecked("trade"),
+        profile_addr: Addr::unchecked("profile"),
+        price_addr: Addr::unchecked("price"),
+        price_provider_addr: Addr::unchecked("price_provider"),
+        local_market_addr: Addr::unchecked("local_market"),
+        local_denom: Denom::Native("factory/local".to_string()),
+        chain_fee_collector_addr: Addr::unchecked("chain_fee_collector"),
+        warchest_addr: Addr::unchecked("warchest"),
+        active_offers_limit: 4,
+        active_trades_limit: 20,
+        arbitration_fee_pct: Decimal::percent(1),
+        burn_fee_pct: Decimal::permille(2),
+        chain_fee_pct: Decimal::permille(3),
+        warchest_fee_pct: Decimal::permille(5),
+        trade_expiration_timer: 1200,
+        trade_dispute_timer: 3600,
+        trade_limit_min: 1,
+        trade_limit_max: 500,
+        config_timelock: 0,
+        trade_price_twap_window: 0,
+        fiat_trade_limits: vec![],
+        denom_trade_limits: vec![],
+    }
+}
+
+#[test]
+fn pause_is_pushed_to_the_registered_contracts() {
+    let mut deps = setup();
+    let pause = ExecuteMsg::UpdatePause {
+        target: PauseTarget::Operation(ProtocolOperation::CreateOffer),
+        paused: true,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone", &[]),
+        pause.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    // Nothing is pushed before the first config
+    let res = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), pause).unwrap();
+    assert!(res.messages.is_empty());
+
+    let update_config = ExecuteMsg::UpdateConfig(config());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap();
+    let pause_contract = ExecuteMsg::UpdatePause {
+        target: PauseTarget::Contract(ProtocolContract::Trade),
+        paused: true,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        pause_contract,
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 4);
+
+    let pause_status: PauseStatus =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap();
+    assert!(pause_status.is_paused(&ProtocolOperation::CreateOffer));
+    assert!(pause_status.is_paused(&ProtocolOperation::FundEscrow));
+    assert!(!pause_status.is_paused(&ProtocolOperation::UpdateOffer));
+
+    let unpause = ExecuteMsg::UpdatePause {
+        target: PauseTarget::Operation(ProtocolOperation::CreateOffer),
+        paused: false,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), unpause).unwrap();
+    let pause_status: PauseStatus =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap();
+    assert!(!pause_status.is_paused(&ProtocolOperation::CreateOffer));
+}
+
+#[test]
+fn config_updates_wait_for_the_timelock() {
+    let mut deps = setup();
+    let mut env = mock_env();
+    let mut config = config();
+    config.config_timelock = 3600;
+    // The first config is applied right away
+    let update_config = ExecuteMsg::UpdateConfig(config.clone());
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    config.trade_limit_max = 1000;
+    let update_config = ExecuteMsg::UpdateConfig(config.clone());
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        update_config.clone(),
+    )
+    .unwrap();
+    let pending_config: Option<PendingConfig> =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::PendingConfig {}).unwrap())
+            .unwrap();
+    let executable_at = env.block.time.seconds() + 3600;
+    assert_eq!(pending_config.unwrap().executable_at, executable_at);
+
+    // A pending update has to be canceled before queuing another one
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ConfigUpdatePending { .. }));
+
+    let execute_update = ExecuteMsg::ExecuteConfigUpdate {};
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        execute_update.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ConfigTimelocked { .. }));
+    let current_config: HubConfig =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(current_config.trade_limit_max, 500);
+
+    env.block.time = env.block.time.plus_seconds(3600);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        execute_update,
+    )
+    .unwrap();
+    let current_config: HubConfig =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(current_config, config);
+    let pending_config: Option<PendingConfig> =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::PendingConfig {}).unwrap()).unwrap();
+    assert_eq!(pending_config, None);
+}
+
+#[test]
+fn admin_is_handed_over_once_accepted() {
+    let mut deps = setup();
+    let update_admin = ExecuteMsg::UpdateAdmin {
+        admin_addr: Addr::unchecked("new_admin"),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        update_admin,
+    )
+    .unwrap();
+    let admin: Admin =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Admin {}).unwrap()).unwrap();
+    assert_eq!(admin.addr, Addr::unchecked("admin"));
+
+    let accept_admin = ExecuteMsg::AcceptAdmin {};
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone", &[]),
+        accept_admin.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_admin", &[]),
+        accept_admin,
+    )
+    .unwrap();
+    let admin: Admin =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Admin {}).unwrap()).unwrap();
+    assert_eq!(admin.addr, Addr::unchecked("new_admin"));
+    let pending_admin: Option<Admin> =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingAdmin {}).unwrap()).unwrap();
+    assert_eq!(pending_admin, None);
+
+    // The previous admin can't act anymore
+    let update_config = ExecuteMsg::UpdateConfig(config());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+}
+
+#[test]
+fn past_configs_are_kept_by_version_and_height() {
+    let mut deps = setup();
+    let mut env = mock_env();
+    let first_height = env.block.height;
+    let update_config = ExecuteMsg::UpdateConfig(config());
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    env.block.height += 10;
+    let mut config = config();
+    config.active_offers_limit = 8;
+    let update_config = ExecuteMsg::UpdateConfig(config.clone());
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "config_version" && attr.value == "2"));
+
+    let versioned_config: VersionedConfig =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::VersionedConfig {}).unwrap())
+            .unwrap();
+    assert_eq!(versioned_config.version, 2);
+    assert_eq!(versioned_config.config, config);
+
+    let config_at = |version: Option<u64>, height: Option<u64>| -> VersionedConfig {
+        let msg = QueryMsg::ConfigAt { version, height };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    assert_eq!(config_at(Some(1), None).config.active_offers_limit, 4);
+    assert_eq!(config_at(None, Some(first_height + 5)).version, 1);
+    assert_eq!(config_at(None, Some(first_height + 10)).version, 2);
+    assert!(query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ConfigAt {
+            version: None,
+            height: Some(first_height - 1),
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn trade_limits_merge_the_fiat_and_denom_overrides() {
+    let mut deps = setup();
+    let kuji = Denom::Native("ukuji".to_string());
+    let mut config = config();
+    config.fiat_trade_limits = vec![(FiatCurrency::BRL, TradeLimits { min: 10, max: 300 })];
+    config.denom_trade_limits = vec![(kuji.clone(), TradeLimits { min: 50, max: 1000 })];
+    let update_config = ExecuteMsg::UpdateConfig(config.clone());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    let trade_limits = |fiat: FiatCurrency, denom: Denom| -> TradeLimits {
+        let msg = QueryMsg::TradeLimits { fiat, denom };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    assert_eq!(
+        trade_limits(FiatCurrency::BRL, kuji.clone()),
+        TradeLimits { min: 50, max: 300 }
+    );
+    assert_eq!(
+        trade_limits(FiatCurrency::ARS, kuji.clone()),
+        TradeLimits { min: 50, max: 1000 }
+    );
+    assert_eq!(
+        trade_limits(FiatCurrency::ARS, Denom::Native("factory/usk".to_string())),
+        TradeLimits { min: 1, max: 500 }
+    );
+
+    // Overrides that leave no valid range are rejected
+    config.fiat_trade_limits = vec![(FiatCurrency::BRL, TradeLimits { min: 10, max: 40 })];
+    let update_config = ExecuteMsg::UpdateConfig(config);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        update_config,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+}
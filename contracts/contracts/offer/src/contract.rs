@@ -1,21 +1,36 @@
 use crate::state::{offers_count_read, offers_count_storage};
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, SubMsg,
+    entry_point, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdResult, SubMsg, Uint128, Uint256,
 };
 use cw2::{get_contract_version, set_contract_version};
+use cw20::Denom;
+use localmoney_protocol::currencies::FiatCurrency;
 use localmoney_protocol::errors::ContractError;
 use localmoney_protocol::errors::ContractError::HubAlreadyRegistered;
 use localmoney_protocol::guards::{
-    assert_migration_parameters, assert_min_g_max, assert_offer_description_valid, assert_ownership,
+    assert_migration_parameters, assert_min_g_max, assert_offer_description_valid,
+    assert_offer_max_inside_trading_limit, assert_ownership, assert_profile_not_suspended,
+    assert_taker_requirements_valid,
+};
+use localmoney_protocol::hub::{
+    HubConfig, HubState, ProtocolOperation, ReputationTier, VersionedConfig,
+};
+use localmoney_protocol::hub_utils::{
+    assert_not_paused, get_hub_config, get_reputation_tiers, register_hub_internal,
+    update_hub_config_internal, update_hub_state_internal,
 };
-use localmoney_protocol::hub_utils::{get_hub_config, register_hub_internal};
 use localmoney_protocol::offer::{
-    offers, ExecuteMsg, InstantiateMsg, MigrateMsg, Offer, OfferModel, OfferMsg, OfferResponse,
-    OfferState, OfferUpdateMsg, OffersCount, QueryMsg,
+    offers, ExecuteMsg, FiatLimits, InstantiateMsg, MigrateMsg, Offer, OfferModel, OfferMsg,
+    OfferResponse, OfferState, OfferUpdateMsg, OffersCount, QueryMsg,
+};
+use localmoney_protocol::price::{
+    denom_amount, fiat_value, price_error_from_query, query_fiat_price_for_denom,
 };
 use localmoney_protocol::profile::{
-    load_profile, update_profile_active_offers_msg, update_profile_contact_msg,
+    load_profile, update_profile_active_offers_msg, update_profile_contact_msg, Profile,
 };
+use localmoney_protocol::trade::calc_denom_fiat_price;
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -44,9 +59,111 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::RegisterHub {} => register_hub(deps, info),
+        ExecuteMsg::UpdateHubConfig(config) => update_hub_config(deps, info, config),
+        ExecuteMsg::UpdateHubState(state) => update_hub_state(deps, info, state),
         ExecuteMsg::Create { offer } => create_offer(deps, env, info, offer),
         ExecuteMsg::UpdateOffer { offer_update } => update_offer(deps, env, info, offer_update),
+        ExecuteMsg::PauseOffersByOwner { owner } => pause_offers_by_owner(deps, info, owner),
+    }
+}
+
+// Called by the profile contract when the owner is suspended
+pub fn pause_offers_by_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Addr,
+) -> Result<Response, ContractError> {
+    let hub_config = get_hub_config(deps.as_ref());
+    assert_ownership(info.sender, hub_config.profile_addr.clone())?;
+
+    let active_offers: Vec<Offer> = offers()
+        .idx
+        .owner
+        .prefix(owner.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .flat_map(|item| item.map(|(_, offer)| offer))
+        .filter(|offer| offer.state == OfferState::Active)
+        .collect();
+
+    let mut sub_msgs: Vec<SubMsg> = Vec::new();
+    for mut offer in active_offers.clone() {
+        offer.state = OfferState::Paused;
+        OfferModel::store(deps.storage, &offer).unwrap();
+        sub_msgs.push(update_profile_active_offers_msg(
+            hub_config.profile_addr.to_string(),
+            owner.clone(),
+            OfferState::Paused,
+        ));
+    }
+
+    let res = Response::new()
+        .add_submessages(sub_msgs)
+        .add_attribute("action", "pause_offers_by_owner")
+        .add_attribute("owner", owner.to_string())
+        .add_attribute("paused_offers", active_offers.len().to_string());
+    Ok(res)
+}
+
+// The maker's reputation tier limits the max amount of its offers, converted to USD.
+fn assert_offer_max_inside_reputation_limit(
+    deps: Deps,
+    env: &Env,
+    hub_config: &HubConfig,
+    owner_profile: &Profile,
+    denom: Denom,
+    max_amount: Uint128,
+) -> Result<(), ContractError> {
+    let reputation_limit = match ReputationTier::trade_limit_for(
+        &get_reputation_tiers(deps),
+        owner_profile,
+        env.block.time.seconds(),
+    ) {
+        Some(reputation_limit) => reputation_limit,
+        None => return Ok(()),
+    };
+
+    let denom_usd_price = query_fiat_price_for_denom(
+        &deps.querier,
+        denom.clone(),
+        FiatCurrency::USD,
+        hub_config.price_addr.to_string(),
+    )
+    .map_err(|e| price_error_from_query(&denom, &FiatCurrency::USD, e))?;
+    let usd_max_amount = fiat_value(max_amount, denom_usd_price.price, denom_usd_price.decimals);
+    assert_offer_max_inside_trading_limit(usd_max_amount, Uint256::from_u128(reputation_limit))
+}
+
+// The max amount of the offer in the denom, fiat limits are converted at the offer price,
+// the current price adjusted by the offer rate, as trades convert them
+fn offer_max_amount(
+    deps: Deps,
+    hub_config: &HubConfig,
+    denom: &Denom,
+    fiat_currency: &FiatCurrency,
+    rate: Uint128,
+    max_amount: Uint128,
+    fiat_limits: &Option<FiatLimits>,
+) -> Result<Uint128, ContractError> {
+    let fiat_limits = match fiat_limits {
+        Some(fiat_limits) => fiat_limits,
+        None => return Ok(max_amount),
+    };
+    let denom_fiat_price = query_fiat_price_for_denom(
+        &deps.querier,
+        denom.clone(),
+        fiat_currency.clone(),
+        hub_config.price_addr.to_string(),
+    )
+    .map_err(|e| price_error_from_query(denom, fiat_currency, e))?;
+    let offer_price = calc_denom_fiat_price(rate, denom_fiat_price.price);
+    if offer_price.is_zero() {
+        return Err(ContractError::InvalidPriceForDenom {});
     }
+    Ok(denom_amount(
+        fiat_limits.max,
+        offer_price,
+        denom_fiat_price.decimals,
+    ))
 }
 
 #[entry_point]
@@ -61,6 +178,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             order,
             limit,
             last,
+            viewer,
         } => to_binary(&OfferModel::query_by(
             deps,
             offer_type,
@@ -69,6 +187,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             order,
             limit,
             last,
+            viewer,
         )?),
         QueryMsg::OffersByOwner { owner, limit, last } => {
             to_binary(&OfferModel::query_by_owner(deps, owner, limit, last)?)
@@ -83,10 +202,44 @@ pub fn create_offer(
     info: MessageInfo,
     msg: OfferMsg,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::CreateOffer)?;
     let hub_config = get_hub_config(deps.as_ref());
     assert_min_g_max(msg.min_amount, msg.max_amount)?;
+    if let Some(fiat_limits) = &msg.fiat_limits {
+        assert_min_g_max(fiat_limits.min, fiat_limits.max)?;
+    }
 
     assert_offer_description_valid(msg.description.clone()).unwrap();
+    assert_taker_requirements_valid(&msg.taker_requirements)?;
+    let owner_profile = load_profile(
+        &deps.querier,
+        hub_config.profile_addr.to_string(),
+        info.sender.clone(),
+    )
+    .unwrap();
+    assert_profile_not_suspended(&owner_profile, env.block.time.seconds())?;
+    // Takers encrypt their contact with the maker key, so the maker must have one
+    if owner_profile.encryption_key.is_none() && msg.owner_encryption_key.is_none() {
+        return Err(ContractError::MissingProfileEncryptionKey {
+            profile_addr: info.sender.clone(),
+        });
+    }
+    assert_offer_max_inside_reputation_limit(
+        deps.as_ref(),
+        &env,
+        &hub_config,
+        &owner_profile,
+        msg.denom.clone(),
+        offer_max_amount(
+            deps.as_ref(),
+            &hub_config,
+            &msg.denom,
+            &msg.fiat_currency,
+            msg.rate,
+            msg.max_amount,
+            &msg.fiat_limits,
+        )?,
+    )?;
 
     // Load offers count to create the next sequential id, maybe we can switch to a hash based id in the future.
     let mut offers_count = offers_count_storage(deps.storage)
@@ -95,13 +248,16 @@ pub fn create_offer(
     offers_count.count += 1;
     let offer_id = offers_count.count;
 
-    // Update profile contact info
-    let update_profile_contact_msg = update_profile_contact_msg(
-        hub_config.profile_addr.to_string(),
-        info.sender.clone(),
-        msg.owner_contact.clone(),
-        msg.owner_encryption_key.clone(),
-    );
+    // Update profile contact info only when the maker overrides it
+    let mut sub_msgs: Vec<SubMsg> = Vec::new();
+    if msg.owner_contact.is_some() || msg.owner_encryption_key.is_some() {
+        sub_msgs.push(update_profile_contact_msg(
+            hub_config.profile_addr.to_string(),
+            info.sender.clone(),
+            msg.owner_contact.clone(),
+            msg.owner_encryption_key.clone(),
+        ));
+    }
 
     let offer = OfferModel::create(
         deps.storage,
@@ -114,9 +270,11 @@ pub fn create_offer(
             denom: msg.denom,
             min_amount: msg.min_amount,
             max_amount: msg.max_amount,
+            fiat_limits: msg.fiat_limits,
             state: OfferState::Active,
             description: msg.description,
             timestamp: env.block.time.seconds(),
+            taker_requirements: msg.taker_requirements,
         },
     )
     .offer;
@@ -127,15 +285,14 @@ pub fn create_offer(
         .unwrap();
 
     // Update profile active offers
-    let update_profile_offers_msg = update_profile_active_offers_msg(
+    sub_msgs.push(update_profile_active_offers_msg(
         hub_config.profile_addr.to_string(),
         info.sender.clone(),
         offer.state,
-    );
+    ));
 
     let res = Response::new()
-        .add_submessage(update_profile_contact_msg)
-        .add_submessage(update_profile_offers_msg)
+        .add_submessages(sub_msgs)
         .add_attribute("action", "create_offer")
         .add_attribute("type", offer.offer_type.to_string())
         .add_attribute("id", offer.id.to_string())
@@ -148,26 +305,64 @@ pub fn create_offer(
 
 pub fn update_offer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: OfferUpdateMsg,
 ) -> Result<Response, ContractError> {
+    let offer = offers()
+        .load(deps.storage, msg.id)
+        .map_err(|_| ContractError::OfferNotFound {
+            offer_id: msg.id.to_string(),
+        })?;
+    assert_ownership(info.sender.clone(), offer.owner.clone())?;
+
+    // Makers can still pause or archive their offers while updates are paused
+    if msg.state == OfferState::Active {
+        assert_not_paused(deps.as_ref(), ProtocolOperation::UpdateOffer)?;
+    }
     assert_min_g_max(msg.min_amount, msg.max_amount)?;
+    if let Some(fiat_limits) = &msg.fiat_limits {
+        assert_min_g_max(fiat_limits.min, fiat_limits.max)?;
+    }
 
     let hub_config = get_hub_config(deps.as_ref());
+    if msg.state == OfferState::Active {
+        let owner_profile = load_profile(
+            &deps.querier,
+            hub_config.profile_addr.to_string(),
+            info.sender.clone(),
+        )
+        .unwrap();
+        assert_profile_not_suspended(&owner_profile, env.block.time.seconds())?;
+        assert_offer_max_inside_reputation_limit(
+            deps.as_ref(),
+            &env,
+            &hub_config,
+            &owner_profile,
+            offer.denom.clone(),
+            offer_max_amount(
+                deps.as_ref(),
+                &hub_config,
+                &offer.denom,
+                &offer.fiat_currency,
+                msg.rate,
+                msg.max_amount,
+                &msg.fiat_limits,
+            )?,
+        )?;
+    }
     let mut offer_model = OfferModel::may_load(deps.storage, msg.id);
 
-    assert_ownership(info.sender.clone(), offer_model.offer.owner.clone())?;
-
     assert_offer_description_valid(msg.description.clone()).unwrap();
+    assert_taker_requirements_valid(&msg.taker_requirements)?;
 
     let mut sub_msgs: Vec<SubMsg> = Vec::new();
-    if msg.owner_contact.is_some() && msg.owner_encryption_key.is_some() {
+    if msg.owner_contact.is_some() || msg.owner_encryption_key.is_some() {
         sub_msgs.push(update_profile_contact_msg(
             hub_config.profile_addr.to_string(),
             info.sender.clone(),
-            msg.owner_contact.clone().unwrap(),
-            msg.owner_encryption_key.clone().unwrap(),
+            msg.owner_contact.clone(),
+            msg.owner_encryption_key.clone(),
         ));
     }
     if msg.state != offer_model.offer.state {
@@ -193,6 +388,22 @@ fn register_hub(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractEr
     register_hub_internal(info.sender, deps.storage, HubAlreadyRegistered {})
 }
 
+fn update_hub_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Box<VersionedConfig>,
+) -> Result<Response, ContractError> {
+    update_hub_config_internal(info.sender, deps.storage, *config)
+}
+
+fn update_hub_state(
+    deps: DepsMut,
+    info: MessageInfo,
+    state: Box<HubState>,
+) -> Result<Response, ContractError> {
+    update_hub_state_internal(info.sender, deps.storage, *state)
+}
+
 fn query_state(deps: Deps) -> StdResult<OffersCount> {
     let state = offers_count_read(deps.storage).load().unwrap();
     Ok(state)
@@ -0,0 +1,265 @@
+use crate::contract::{execute, instantiate};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{
+    to_binary, Addr, ContractResult, Decimal, MemoryStorage, OwnedDeps, Response, SystemResult,
+    Uint128, Uint256, WasmQuery,
+};
+use cw20::Denom;
+use localmoney_protocol::currencies::FiatCurrency;
+use localmoney_protocol::errors::ContractError;
+use localmoney_protocol::hub::{
+    Admin, HubConfig, HubState, PauseStatus, ProtocolOperation, ReputationTier, VersionedConfig,
+};
+use localmoney_protocol::offer::{
+    ExecuteMsg, InstantiateMsg, OfferMsg, OfferState, OfferType, OfferUpdateMsg,
+};
+use localmoney_protocol::price::DenomFiatPrice;
+use localmoney_protocol::profile::Profile;
+
+type MockDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+fn setup() -> MockDeps {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+    let register_hub = ExecuteMsg::RegisterHub {};
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        register_hub,
+    )
+    .unwrap();
+    push_config(&mut deps, 1, "profile").unwrap();
+    push_state(&mut deps, hub_state(1)).unwrap();
+    deps
+}
+
+fn push_config(
+    deps: &mut MockDeps,
+    version: u64,
+    profile_addr: &str,
+) -> Result<Response, ContractError> {
+    let config = VersionedConfig {
+        version,
+        height: 0,
+        time: 0,
+        config: HubConfig {
+            offer_addr: Addr::unchecked("offer"),
+            trade_addr: Addr::unchecked("trade"),
+            profile_addr: Addr::unchecked(profile_addr),
+            price_addr: Addr::unchecked("price"),
+            price_provider_addr: Addr::unchecked("price_provider"),
+            local_market_addr: Addr::unchecked("local_market"),
+            local_denom: Denom::Native("factory/local".to_string()),
+            chain_fee_collector_addr: Addr::unchecked("chain_fee_collector"),
+            warchest_addr: Addr::unchecked("warchest"),
+            active_offers_limit: 4,
+            active_trades_limit: 20,
+            arbitration_fee_pct: Decimal::percent(1),
+            burn_fee_pct: Decimal::permille(2),
+            chain_fee_pct: Decimal::permille(3),
+            warchest_fee_pct: Decimal::permille(5),
+            trade_expiration_timer: 1200,
+            trade_dispute_timer: 3600,
+            trade_limit_min: 1,
+            trade_limit_max: 500,
+            config_timelock: 0,
+            trade_price_twap_window: 0,
+            fiat_trade_limits: vec![],
+            denom_trade_limits: vec![],
+        },
+    };
+    let msg = ExecuteMsg::UpdateHubConfig(Box::new(config));
+    execute(deps.as_mut(), mock_env(), mock_info("hub", &[]), msg)
+}
+
+fn hub_state(version: u64) -> HubState {
+    HubState {
+        version,
+        admin: Admin {
+            addr: Addr::unchecked("admin"),
+        },
+        pause_status: PauseStatus::default(),
+        reputation_tiers: vec![],
+        moderator: None,
+        attesters: vec![],
+    }
+}
+
+fn push_state(deps: &mut MockDeps, state: HubState) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::UpdateHubState(Box::new(state));
+    execute(deps.as_mut(), mock_env(), mock_info("hub", &[]), msg)
+}
+
+fn offer_msg(max_amount: u128) -> OfferMsg {
+    OfferMsg {
+        offer_type: OfferType::Sell,
+        owner_contact: None,
+        owner_encryption_key: None,
+        fiat_currency: FiatCurrency::BRL,
+        rate: Uint128::new(100),
+        denom: Denom::Native("ukuji".to_string()),
+        min_amount: Uint128::new(1_000_000),
+        max_amount: Uint128::new(max_amount),
+        fiat_limits: None,
+        description: None,
+        taker_requirements: None,
+    }
+}
+
+// The profile contract returns `profile` and the price contract prices ukuji at 1.00 USD
+fn mock_queries(deps: &mut MockDeps, profile: Profile) {
+    deps.querier.update_wasm(move |query| {
+        let response = match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "profile" => {
+                to_binary(&profile)
+            }
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "price" => {
+                to_binary(&DenomFiatPrice {
+                    denom: Denom::Native("ukuji".to_string()),
+                    fiat: FiatCurrency::USD,
+                    price: Uint256::from_u128(100),
+                    decimals: 6,
+                })
+            }
+            _ => panic!("Unexpected query: {:?}", query),
+        };
+        SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+    });
+}
+
+#[test]
+fn hub_pushes_that_are_not_newer_are_ignored() {
+    let mut deps = setup();
+    push_config(&mut deps, 3, "profile").unwrap();
+    let res = push_config(&mut deps, 2, "old_profile").unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "ignored_config_version" && attr.value == "2"));
+    // The cached config is still the newest one
+    let pause_offers = ExecuteMsg::PauseOffersByOwner {
+        owner: Addr::unchecked("maker"),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("old_profile", &[]),
+        pause_offers.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("profile", &[]),
+        pause_offers,
+    )
+    .unwrap();
+
+    let mut state = hub_state(3);
+    state
+        .pause_status
+        .operations
+        .push(ProtocolOperation::CreateOffer);
+    push_state(&mut deps, state).unwrap();
+    let res = push_state(&mut deps, hub_state(3)).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "ignored_state_version" && attr.value == "3"));
+    let create = ExecuteMsg::Create {
+        offer: offer_msg(5_000_000),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("maker", &[]), create).unwrap_err();
+    assert!(matches!(err, ContractError::OperationPaused { .. }));
+
+    // Only the registered hub can push
+    let msg = ExecuteMsg::UpdateHubState(Box::new(hub_state(4)));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("someone", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+}
+
+#[test]
+fn offers_are_limited_by_the_reputation_tier_reached() {
+    let mut deps = setup();
+    let tier = |min_released_trades: u64, trade_limit_max: u128| ReputationTier {
+        min_released_trades,
+        min_account_age: 0,
+        max_disputes_lost: 0,
+        trade_limit_max,
+    };
+    let mut state = hub_state(2);
+    state.reputation_tiers = vec![tier(0, 100), tier(10, 1000)];
+    push_state(&mut deps, state).unwrap();
+    let mut profile = Profile::new(Addr::unchecked("maker"), 0);
+    profile.encryption_key = Some("a2V5".to_string());
+    mock_queries(&mut deps, profile.clone());
+
+    // 200 USD is above the 100 USD of the first tier
+    let create = ExecuteMsg::Create {
+        offer: offer_msg(200_000_000),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        create.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::OfferMaxAboveTradingLimit { .. }
+    ));
+    let create_within_limit = ExecuteMsg::Create {
+        offer: offer_msg(100_000_000),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        create_within_limit,
+    )
+    .unwrap();
+
+    // The highest tier reached applies
+    profile.released_trades_count = 10;
+    mock_queries(&mut deps, profile);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        create.clone(),
+    )
+    .unwrap();
+
+    // Below every tier, only the hub limits apply
+    let mut state = hub_state(3);
+    state.reputation_tiers = vec![tier(20, 100)];
+    push_state(&mut deps, state).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("maker", &[]), create).unwrap();
+
+    // Only the owner can update the offer, whatever the update
+    let update = ExecuteMsg::UpdateOffer {
+        offer_update: OfferUpdateMsg {
+            id: 1,
+            owner_contact: None,
+            owner_encryption_key: None,
+            rate: Uint128::new(100),
+            min_amount: Uint128::new(1_000_000),
+            max_amount: Uint128::new(200_000_000),
+            fiat_limits: None,
+            state: OfferState::Active,
+            description: None,
+            taker_requirements: None,
+        },
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("taker", &[]), update).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+}
@@ -1,34 +1,49 @@
 use std::ops::{Div, Mul};
 
+use crate::sources::{quote, usd_price};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128, Uint256,
+    to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Storage, Uint128, Uint256,
 };
 use cw2::{get_contract_version, set_contract_version};
 use cw20::Denom;
-use localmoney_protocol::constants::BASE_ORACLE_DENOM;
+use cw_storage_plus::Bound;
+use localmoney_protocol::constants::{
+    BASE_ORACLE_DECIMALS, BASE_ORACLE_DENOM, DEFAULT_DENOM_DECIMALS, PRICE_HISTORY_LIMIT,
+};
 use localmoney_protocol::currencies::FiatCurrency;
 use localmoney_protocol::denom_utils::denom_to_string;
 use localmoney_protocol::errors::ContractError;
 use localmoney_protocol::errors::ContractError::HubAlreadyRegistered;
-use localmoney_protocol::guards::{assert_migration_parameters, assert_ownership};
-use localmoney_protocol::hub_utils::{get_hub_admin, get_hub_config, register_hub_internal};
-use localmoney_protocol::kujira::asset::{Asset, AssetInfo};
-use localmoney_protocol::kujira::denom::Denom as KujiraDenom;
-use localmoney_protocol::kujira::fin::QueryMsg as FinQueryMsg;
-use localmoney_protocol::kujira::fin::SimulationResponse;
+use localmoney_protocol::guards::{
+    assert_decimals_valid, assert_migration_parameters, assert_ownership, assert_route_chains,
+    assert_route_swaps, validate_min_max_items_per_page,
+};
+use localmoney_protocol::hub::{HubConfig, HubState, ProtocolOperation, VersionedConfig};
+use localmoney_protocol::hub_utils::{
+    assert_not_paused, get_hub_admin, get_hub_config, register_hub_internal,
+    update_hub_config_internal, update_hub_state_internal,
+};
 use localmoney_protocol::kujira::msg::KujiraMsg;
-use localmoney_protocol::kujira::querier::KujiraQuerier;
 use localmoney_protocol::kujira::query::KujiraQuery;
 use localmoney_protocol::price::{
-    CurrencyPrice, DenomFiatPrice, ExecuteMsg, PriceRoute, QueryMsg, DENOM_PRICE_ROUTE, FIAT_PRICE,
+    price_error_from_query, price_query_error, price_query_error_payload, BaseOracle,
+    CurrencyPrice, DenomFiatPrice, DenomFiatPriceResponse, DenomMetadata, DenomPriceSample,
+    ExecuteMsg, MigrateMsg, PendingPrice, PriceAggregation, PriceRoute, PriceSource,
+    PriceSubmission, PushedPrice, QueryMsg, BASE_ORACLE, DENOM_METADATA, DENOM_PRICE_HISTORY,
+    DENOM_PRICE_HISTORY_COUNT, DENOM_PRICE_ROUTE, FIAT_MAX_PRICE_CHANGE, FIAT_PRICE,
+    FIAT_PRICE_HISTORY, FIAT_PRICE_HISTORY_COUNT, FIAT_PRICE_MAX_AGE, PENDING_FIAT_PRICE,
+    PRICE_AGGREGATION, PRICE_PROVIDERS, PRICE_SUBMISSIONS, PUSHED_PRICES, PUSHED_PRICE_MAX_AGE,
 };
-use localmoney_protocol::profile::{InstantiateMsg, MigrateMsg};
+use localmoney_protocol::profile::InstantiateMsg;
 
 // version info for migration info
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// USD and base asset prices are in 10^12 units per whole denom
+const PRICE_PRECISION: u128 = 1_000_000_000_000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -51,19 +66,84 @@ pub fn execute(
 ) -> Result<Response<KujiraMsg>, ContractError> {
     match msg {
         ExecuteMsg::RegisterHub {} => register_hub(deps, info),
+        ExecuteMsg::UpdateHubConfig(config) => update_hub_config(deps, info, config),
+        ExecuteMsg::UpdateHubState(state) => update_hub_state(deps, info, state),
         ExecuteMsg::UpdatePrices(prices) => update_prices(deps, env, info, prices),
         ExecuteMsg::RegisterPriceRouteForDenom { denom, route } => {
-            register_price_route_for_denom(deps, info, denom, route)
+            register_price_route_for_denom(deps, env, info, denom, route)
+        }
+        ExecuteMsg::RegisterDenomMetadata { denom, metadata } => {
+            register_denom_metadata(deps, env, info, denom, metadata)
+        }
+        ExecuteMsg::UpdateBaseOracle(base_oracle) => update_base_oracle(deps, info, base_oracle),
+        ExecuteMsg::PushPrice { symbol, usd_price } => {
+            push_price(deps, env, info, symbol, usd_price)
+        }
+        ExecuteMsg::UpdateFiatPriceMaxAge { fiat, max_age } => {
+            update_fiat_price_max_age(deps, info, fiat, max_age)
+        }
+        ExecuteMsg::UpdatePushedPriceMaxAge { symbol, max_age } => {
+            update_pushed_price_max_age(deps, info, symbol, max_age)
+        }
+        ExecuteMsg::UpdatePriceProvider { provider, enabled } => {
+            update_price_provider(deps, env, info, provider, enabled)
+        }
+        ExecuteMsg::UpdatePriceAggregation(aggregation) => {
+            update_price_aggregation(deps, info, aggregation)
+        }
+        ExecuteMsg::UpdateFiatMaxPriceChange { fiat, max_change } => {
+            update_fiat_max_price_change(deps, info, fiat, max_change)
         }
+        ExecuteMsg::ConfirmPendingPrice { fiat } => confirm_pending_price(deps, env, info, fiat),
+        ExecuteMsg::RejectPendingPrice { fiat } => reject_pending_price(deps, info, fiat),
+        ExecuteMsg::SampleDenomPrices { denoms } => sample_denom_prices(deps, env, info, denoms),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<KujiraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<KujiraQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Price { fiat, denom } => {
-            to_binary(&query_fiat_price_for_denom(deps, fiat, denom)?)
+            to_binary(&query_fiat_price_for_denom(deps, env, fiat, denom)?)
         }
+        QueryMsg::Prices { denom, fiats } => to_binary(&query_prices(deps, env, denom, fiats)?),
+        QueryMsg::AllFiatPrices { limit, last } => {
+            to_binary(&query_all_fiat_prices(deps, limit, last)?)
+        }
+        QueryMsg::DenomMetadata { denom } => {
+            to_binary(&may_load_denom_metadata(deps.storage, &denom)?)
+        }
+        QueryMsg::BaseOracle {} => to_binary(&load_base_oracle(deps.storage)?),
+        QueryMsg::PushedPrice { symbol } => {
+            to_binary(&PUSHED_PRICES.may_load(deps.storage, symbol.as_str())?)
+        }
+        QueryMsg::FiatPriceMaxAge { fiat } => {
+            to_binary(&FIAT_PRICE_MAX_AGE.may_load(deps.storage, fiat.to_string().as_str())?)
+        }
+        QueryMsg::PushedPriceMaxAge { symbol } => {
+            to_binary(&PUSHED_PRICE_MAX_AGE.may_load(deps.storage, symbol.as_str())?)
+        }
+        QueryMsg::PriceProviders {} => to_binary(&query_price_providers(deps)?),
+        QueryMsg::PriceAggregation {} => to_binary(
+            &PRICE_AGGREGATION
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::PriceSubmissions { fiat } => to_binary(&query_price_submissions(deps, fiat)?),
+        QueryMsg::FiatMaxPriceChange { fiat } => {
+            to_binary(&FIAT_MAX_PRICE_CHANGE.may_load(deps.storage, fiat.to_string().as_str())?)
+        }
+        QueryMsg::PendingPrice { fiat } => {
+            to_binary(&PENDING_FIAT_PRICE.may_load(deps.storage, fiat.to_string().as_str())?)
+        }
+        QueryMsg::PriceAt { fiat, denom, time } => {
+            to_binary(&query_price_at(deps, env, fiat, denom, time)?)
+        }
+        QueryMsg::Twap {
+            fiat,
+            denom,
+            window,
+        } => to_binary(&query_twap(deps, env, fiat, denom, window)?),
     }
 }
 
@@ -74,35 +154,539 @@ fn register_hub(
     register_hub_internal(info.sender, deps.storage, HubAlreadyRegistered {})
 }
 
+fn update_hub_config(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    config: Box<VersionedConfig>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    update_hub_config_internal(info.sender, deps.storage, *config)
+}
+
+fn update_hub_state(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    state: Box<HubState>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    update_hub_state_internal(info.sender, deps.storage, *state)
+}
+
 pub fn update_prices(
     deps: DepsMut<KujiraQuery>,
     env: Env,
     info: MessageInfo,
     prices: Vec<CurrencyPrice>,
 ) -> Result<Response<KujiraMsg>, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::UpdatePrices)?;
     let hub_cfg = get_hub_config(deps.as_ref());
-    assert_ownership(info.sender, hub_cfg.price_provider_addr)?;
-    let mut attrs: Vec<(&str, String)> = vec![("action", "update_prices".to_string())];
-    prices.iter().for_each(|price| {
-        // Load existing object or default
-        let path = FIAT_PRICE.key(price.currency.to_string().as_str());
-        let mut currency_price = path
-            .load(deps.storage)
-            .unwrap_or(CurrencyPrice::new(price.currency.clone()));
-
-        // Update price
-        currency_price.usd_price = price.usd_price;
-        currency_price.updated_at = env.block.time.seconds();
-        path.save(deps.storage, &currency_price).unwrap();
-        attrs.push(("currency", price.currency.to_string()));
+    if !is_price_provider(deps.as_ref(), &hub_cfg, &info.sender) {
+        return Err(ContractError::UnauthorizedPriceProvider {
+            caller: info.sender,
+        });
+    }
+    let aggregation = PRICE_AGGREGATION
+        .may_load(deps.storage)
+        .unwrap()
+        .unwrap_or_default();
+    let now = env.block.time.seconds();
+    let mut attrs: Vec<(&str, String)> = vec![
+        ("action", "update_prices".to_string()),
+        ("provider", info.sender.to_string()),
+    ];
+    for price in prices.iter() {
+        let fiat = price.currency.to_string();
+        let submission = CurrencyPrice {
+            currency: price.currency.clone(),
+            usd_price: price.usd_price,
+            updated_at: now,
+        };
+        PRICE_SUBMISSIONS
+            .save(deps.storage, (fiat.as_str(), &info.sender), &submission)
+            .unwrap();
+        attrs.push(("currency", fiat.clone()));
         attrs.push(("usd_price", price.usd_price.to_string()));
-    });
+
+        // A halted price stays pending until it's confirmed or rejected, the submissions
+        // received meanwhile are only aggregated after that
+        if PENDING_FIAT_PRICE.has(deps.storage, fiat.as_str()) {
+            attrs.push(("halted", fiat));
+            continue;
+        }
+
+        // The fiat price only changes once enough providers agree on it
+        match aggregate_price(deps.as_ref(), &hub_cfg, &aggregation, &fiat, now) {
+            Some(usd_price) => {
+                let currency_price = CurrencyPrice {
+                    currency: price.currency.clone(),
+                    usd_price,
+                    updated_at: now,
+                };
+                let previous_usd_price = FIAT_PRICE
+                    .may_load(deps.storage, fiat.as_str())
+                    .unwrap()
+                    .map_or(Uint128::zero(), |p| p.usd_price);
+                if exceeds_max_price_change(deps.as_ref(), &fiat, previous_usd_price, usd_price) {
+                    // Held until an admin or another provider confirms it
+                    let pending_price = PendingPrice {
+                        price: currency_price,
+                        previous_usd_price,
+                        proposed_by: info.sender.clone(),
+                    };
+                    PENDING_FIAT_PRICE
+                        .save(deps.storage, fiat.as_str(), &pending_price)
+                        .unwrap();
+                    attrs.push(("halted", fiat));
+                } else {
+                    save_fiat_price(deps.storage, fiat.as_str(), &currency_price);
+                    attrs.push(("aggregated_usd_price", usd_price.to_string()));
+                }
+            }
+            None => attrs.push(("quorum_pending", fiat)),
+        }
+    }
+    let res = Response::new().add_attributes(attrs);
+    Ok(res)
+}
+
+fn save_fiat_price(storage: &mut dyn Storage, fiat: &str, currency_price: &CurrencyPrice) {
+    FIAT_PRICE.save(storage, fiat, currency_price).unwrap();
+    let count = FIAT_PRICE_HISTORY_COUNT
+        .may_load(storage, fiat)
+        .unwrap()
+        .unwrap_or(0);
+    FIAT_PRICE_HISTORY
+        .save(storage, (fiat, count % PRICE_HISTORY_LIMIT), currency_price)
+        .unwrap();
+    FIAT_PRICE_HISTORY_COUNT
+        .save(storage, fiat, &count.wrapping_add(1))
+        .unwrap();
+}
+
+// Records the spot price of each denom in the base asset, only providers can sample
+// to keep the history meaningful
+pub fn sample_denom_prices(
+    deps: DepsMut<KujiraQuery>,
+    env: Env,
+    info: MessageInfo,
+    denoms: Vec<Denom>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let hub_cfg = get_hub_config(deps.as_ref());
+    if !is_price_provider(deps.as_ref(), &hub_cfg, &info.sender) {
+        return Err(ContractError::UnauthorizedPriceProvider {
+            caller: info.sender,
+        });
+    }
+    let mut attrs: Vec<(&str, String)> = vec![("action", "sample_denom_prices".to_string())];
+    for denom in denoms.iter() {
+        let denom_str = denom_to_string(denom);
+        let sample = DenomPriceSample {
+            base_price: query_denom_base_price(deps.as_ref(), env.block.time.seconds(), denom)
+                .map_err(|e| price_error_from_query(denom, &FiatCurrency::USD, e))?,
+            timestamp: env.block.time.seconds(),
+        };
+        let count = DENOM_PRICE_HISTORY_COUNT
+            .may_load(deps.storage, denom_str.as_str())
+            .unwrap()
+            .unwrap_or(0);
+        DENOM_PRICE_HISTORY
+            .save(
+                deps.storage,
+                (denom_str.as_str(), count % PRICE_HISTORY_LIMIT),
+                &sample,
+            )
+            .unwrap();
+        DENOM_PRICE_HISTORY_COUNT
+            .save(deps.storage, denom_str.as_str(), &count.wrapping_add(1))
+            .unwrap();
+        attrs.push(("denom", denom_str));
+        attrs.push(("base_price", sample.base_price.to_string()));
+    }
     let res = Response::new().add_attributes(attrs);
     Ok(res)
 }
 
+fn exceeds_max_price_change(
+    deps: Deps<KujiraQuery>,
+    fiat: &str,
+    previous_usd_price: Uint128,
+    usd_price: Uint128,
+) -> bool {
+    let max_change = FIAT_MAX_PRICE_CHANGE.may_load(deps.storage, fiat).unwrap();
+    match max_change {
+        Some(max_change) if !previous_usd_price.is_zero() => {
+            let change = usd_price.u128().abs_diff(previous_usd_price.u128());
+            Decimal::from_ratio(change, previous_usd_price) > max_change
+        }
+        _ => false,
+    }
+}
+
+// The hub price provider is always allowed to submit prices
+fn is_price_provider(deps: Deps<KujiraQuery>, hub_cfg: &HubConfig, addr: &Addr) -> bool {
+    addr.eq(&hub_cfg.price_provider_addr) || PRICE_PROVIDERS.has(deps.storage, addr)
+}
+
+fn aggregate_price(
+    deps: Deps<KujiraQuery>,
+    hub_cfg: &HubConfig,
+    aggregation: &PriceAggregation,
+    fiat: &str,
+    now: u64,
+) -> Option<Uint128> {
+    let prices: Vec<Uint128> = PRICE_SUBMISSIONS
+        .prefix(fiat)
+        .range(deps.storage, None, None, Order::Ascending)
+        .flatten()
+        .filter(|(provider, submission)| {
+            is_price_provider(deps, hub_cfg, provider)
+                && !submission.is_stale(now, aggregation.max_submission_age)
+        })
+        .map(|(_, submission)| submission.usd_price)
+        .collect();
+    let quorum = aggregation.quorum as usize;
+    if prices.len() < quorum {
+        return None;
+    }
+    let prices = match aggregation.max_deviation {
+        Some(max_deviation) => within_deviation(prices, max_deviation),
+        None => prices,
+    };
+    if prices.len() < quorum {
+        return None;
+    }
+    Some(median(prices))
+}
+
+// Drops the prices that are further than `max_deviation` from the median
+pub(crate) fn within_deviation(prices: Vec<Uint128>, max_deviation: Decimal) -> Vec<Uint128> {
+    let median_price = median(prices.clone());
+    prices
+        .into_iter()
+        .filter(|price| {
+            median_price.is_zero()
+                || Decimal::from_ratio(price.u128().abs_diff(median_price.u128()), median_price)
+                    <= max_deviation
+        })
+        .collect()
+}
+
+pub(crate) fn median(mut prices: Vec<Uint128>) -> Uint128 {
+    prices.sort();
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        prices[middle]
+    } else {
+        (prices[middle - 1] + prices[middle]) / Uint128::new(2)
+    }
+}
+
+pub fn update_price_provider(
+    deps: DepsMut<KujiraQuery>,
+    env: Env,
+    info: MessageInfo,
+    provider: Addr,
+    enabled: bool,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+
+    if enabled {
+        PRICE_PROVIDERS
+            .save(deps.storage, &provider, &env.block.time.seconds())
+            .unwrap();
+    } else {
+        PRICE_PROVIDERS.remove(deps.storage, &provider);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_price_provider")
+        .add_attribute("provider", provider.to_string())
+        .add_attribute("enabled", enabled.to_string());
+    Ok(res)
+}
+
+// Passing None disables the circuit breaker for the fiat
+pub fn update_fiat_max_price_change(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    fiat: FiatCurrency,
+    max_change: Option<Decimal>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+
+    let fiat_str = fiat.to_string();
+    match max_change {
+        Some(max_change) => FIAT_MAX_PRICE_CHANGE
+            .save(deps.storage, fiat_str.as_str(), &max_change)
+            .unwrap(),
+        None => FIAT_MAX_PRICE_CHANGE.remove(deps.storage, fiat_str.as_str()),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_fiat_max_price_change")
+        .add_attribute("fiat", fiat_str)
+        .add_attribute(
+            "max_change",
+            max_change.map(|m| m.to_string()).unwrap_or_default(),
+        );
+    Ok(res)
+}
+
+pub fn confirm_pending_price(
+    deps: DepsMut<KujiraQuery>,
+    env: Env,
+    info: MessageInfo,
+    fiat: FiatCurrency,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let pending_price = load_pending_price(deps.as_ref(), &info.sender, &fiat)?;
+    let fiat_str = fiat.to_string();
+    let currency_price = CurrencyPrice {
+        updated_at: env.block.time.seconds(),
+        ..pending_price.price
+    };
+    save_fiat_price(deps.storage, fiat_str.as_str(), &currency_price);
+    PENDING_FIAT_PRICE.remove(deps.storage, fiat_str.as_str());
+
+    let res = Response::new()
+        .add_attribute("action", "confirm_pending_price")
+        .add_attribute("fiat", fiat_str)
+        .add_attribute("usd_price", currency_price.usd_price.to_string())
+        .add_attribute("confirmed_by", info.sender.to_string());
+    Ok(res)
+}
+
+pub fn reject_pending_price(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    fiat: FiatCurrency,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    load_pending_price(deps.as_ref(), &info.sender, &fiat)?;
+    let fiat_str = fiat.to_string();
+    PENDING_FIAT_PRICE.remove(deps.storage, fiat_str.as_str());
+
+    let res = Response::new()
+        .add_attribute("action", "reject_pending_price")
+        .add_attribute("fiat", fiat_str)
+        .add_attribute("rejected_by", info.sender.to_string());
+    Ok(res)
+}
+
+// A pending price can be handled by the admin or by a provider other than the one who proposed it
+fn load_pending_price(
+    deps: Deps<KujiraQuery>,
+    sender: &Addr,
+    fiat: &FiatCurrency,
+) -> Result<PendingPrice, ContractError> {
+    let pending_price = PENDING_FIAT_PRICE
+        .may_load(deps.storage, fiat.to_string().as_str())
+        .unwrap()
+        .ok_or(ContractError::NoPendingPrice { fiat: fiat.clone() })?;
+    let admin = get_hub_admin(deps).addr;
+    if sender.eq(&admin) {
+        return Ok(pending_price);
+    }
+    let hub_cfg = get_hub_config(deps);
+    if !is_price_provider(deps, &hub_cfg, sender) || sender.eq(&pending_price.proposed_by) {
+        return Err(ContractError::UnauthorizedPriceProvider {
+            caller: sender.clone(),
+        });
+    }
+    Ok(pending_price)
+}
+
+pub fn update_price_aggregation(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    aggregation: PriceAggregation,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+    if aggregation.quorum == 0 {
+        return Err(ContractError::InvalidParameter {
+            parameter: "quorum".to_string(),
+            message: Some("The quorum must be at least 1.".to_string()),
+        });
+    }
+    // Without a max age, old submissions would keep counting towards the quorum
+    if aggregation.quorum > 1 && aggregation.max_submission_age == 0 {
+        return Err(ContractError::InvalidParameter {
+            parameter: "max_submission_age".to_string(),
+            message: Some(
+                "The max submission age must be set when the quorum is over 1.".to_string(),
+            ),
+        });
+    }
+    PRICE_AGGREGATION.save(deps.storage, &aggregation).unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "update_price_aggregation")
+        .add_attribute("quorum", aggregation.quorum.to_string())
+        .add_attribute(
+            "max_deviation",
+            aggregation
+                .max_deviation
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+        )
+        .add_attribute(
+            "max_submission_age",
+            aggregation.max_submission_age.to_string(),
+        );
+    Ok(res)
+}
+
+// Passing None disables the staleness check for the fiat
+pub fn update_fiat_price_max_age(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    fiat: FiatCurrency,
+    max_age: Option<u64>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+
+    let fiat_str = fiat.to_string();
+    match max_age {
+        Some(max_age) => FIAT_PRICE_MAX_AGE
+            .save(deps.storage, fiat_str.as_str(), &max_age)
+            .unwrap(),
+        None => FIAT_PRICE_MAX_AGE.remove(deps.storage, fiat_str.as_str()),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_fiat_price_max_age")
+        .add_attribute("fiat", fiat_str)
+        .add_attribute(
+            "max_age",
+            max_age.map(|m| m.to_string()).unwrap_or_default(),
+        );
+    Ok(res)
+}
+
+pub fn update_pushed_price_max_age(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    symbol: String,
+    max_age: Option<u64>,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+
+    match max_age {
+        Some(max_age) => PUSHED_PRICE_MAX_AGE
+            .save(deps.storage, symbol.as_str(), &max_age)
+            .unwrap(),
+        None => PUSHED_PRICE_MAX_AGE.remove(deps.storage, symbol.as_str()),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_pushed_price_max_age")
+        .add_attribute("symbol", symbol)
+        .add_attribute(
+            "max_age",
+            max_age.map(|m| m.to_string()).unwrap_or_default(),
+        );
+    Ok(res)
+}
+
+pub fn register_denom_metadata(
+    deps: DepsMut<KujiraQuery>,
+    env: Env,
+    info: MessageInfo,
+    denom: Denom,
+    metadata: DenomMetadata,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+    assert_decimals_valid(metadata.decimals)?;
+    assert_price_route_valid(
+        deps.as_ref(),
+        env.block.time.seconds(),
+        &denom,
+        metadata.decimals,
+        &metadata.route,
+    )?;
+
+    let denom_str = denom_to_string(&denom);
+    DENOM_METADATA
+        .save(deps.storage, denom_str.as_str(), &metadata)
+        .unwrap();
+    DENOM_PRICE_ROUTE.remove(deps.storage, denom_str.as_str());
+
+    let mut attrs = vec![
+        ("action".to_string(), "register_denom_metadata".to_string()),
+        ("denom".to_string(), denom_str),
+        ("decimals".to_string(), metadata.decimals.to_string()),
+    ];
+    metadata
+        .route
+        .iter()
+        .for_each(|step| attrs.push(("route_step".to_string(), step.to_string())));
+    let res = Response::default().add_attributes(attrs);
+    Ok(res)
+}
+
+pub fn update_base_oracle(
+    deps: DepsMut<KujiraQuery>,
+    info: MessageInfo,
+    base_oracle: BaseOracle,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+    assert_decimals_valid(base_oracle.decimals)?;
+    if base_oracle.source.hop().is_some() {
+        return Err(ContractError::InvalidParameter {
+            parameter: "source".to_string(),
+            message: Some("The base oracle must be priced by a USD source.".to_string()),
+        });
+    }
+    BASE_ORACLE.save(deps.storage, &base_oracle).unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "update_base_oracle")
+        .add_attribute("source", base_oracle.source.to_string())
+        .add_attribute(
+            "denom",
+            base_oracle
+                .denom
+                .map(|d| denom_to_string(&d))
+                .unwrap_or_default(),
+        )
+        .add_attribute("decimals", base_oracle.decimals.to_string());
+    Ok(res)
+}
+
+// Sets the USD price read by the AdminPushed sources under `symbol`
+pub fn push_price(
+    deps: DepsMut<KujiraQuery>,
+    env: Env,
+    info: MessageInfo,
+    symbol: String,
+    usd_price: Decimal,
+) -> Result<Response<KujiraMsg>, ContractError> {
+    let admin = get_hub_admin(deps.as_ref()).addr;
+    assert_ownership(info.sender, admin)?;
+    if usd_price.is_zero() {
+        return Err(ContractError::InvalidPriceForDenom {});
+    }
+    let pushed_price = PushedPrice {
+        usd_price,
+        updated_at: env.block.time.seconds(),
+    };
+    PUSHED_PRICES
+        .save(deps.storage, symbol.as_str(), &pushed_price)
+        .unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "push_price")
+        .add_attribute("symbol", symbol)
+        .add_attribute("usd_price", usd_price.to_string());
+    Ok(res)
+}
+
 pub fn register_price_route_for_denom(
     deps: DepsMut<KujiraQuery>,
+    env: Env,
     info: MessageInfo,
     denom: Denom,
     route: Vec<PriceRoute>,
@@ -110,10 +694,25 @@ pub fn register_price_route_for_denom(
     let admin = get_hub_admin(deps.as_ref()).addr;
     assert_ownership(info.sender, admin)?;
 
+    // Keeps the decimals the denom may already have
+    let mut metadata = load_denom_metadata(deps.storage, &denom).unwrap_or(DenomMetadata {
+        decimals: DEFAULT_DENOM_DECIMALS,
+        route: vec![],
+    });
+    let route: Vec<PriceSource> = route.into_iter().map(PriceSource::from).collect();
+    assert_price_route_valid(
+        deps.as_ref(),
+        env.block.time.seconds(),
+        &denom,
+        metadata.decimals,
+        &route,
+    )?;
+    metadata.route = route.clone();
     let denom_str = denom_to_string(&denom.clone());
-    DENOM_PRICE_ROUTE
-        .save(deps.storage, denom_str.as_str(), &route)
+    DENOM_METADATA
+        .save(deps.storage, denom_str.as_str(), &metadata)
         .unwrap();
+    DENOM_PRICE_ROUTE.remove(deps.storage, denom_str.as_str());
 
     let mut attrs = vec![
         ("action".to_string(), "register_price".to_string()),
@@ -126,70 +725,453 @@ pub fn register_price_route_for_denom(
     Ok(res)
 }
 
+// Dry-runs the route, which is either a single USD source
+// or swap hops chaining from the denom to the base oracle denom
+fn assert_price_route_valid(
+    deps: Deps<KujiraQuery>,
+    now: u64,
+    denom: &Denom,
+    decimals: u8,
+    route: &[PriceSource],
+) -> Result<(), ContractError> {
+    let invalid_route = |message: String| ContractError::InvalidRoute {
+        denom: denom_to_string(denom),
+        message,
+    };
+    if let [source] = route {
+        if source.hop().is_none() {
+            return usd_price(deps, now, source)
+                .map(|_| ())
+                .map_err(|e| invalid_route(format!("The source {} has no price: {}", source, e)));
+        }
+    }
+
+    let base_denom = load_base_oracle(deps.storage)
+        .map_err(|e| invalid_route(format!("The base oracle can't be loaded: {}", e)))?
+        .denom
+        .ok_or_else(|| {
+            invalid_route("The base oracle denom must be set to register routes.".to_string())
+        })?;
+    let hops = route
+        .iter()
+        .map(|source| {
+            source.hop().ok_or_else(|| {
+                invalid_route(format!(
+                    "The USD source {} can only be the whole route.",
+                    source
+                ))
+            })
+        })
+        .collect::<Result<Vec<(&Denom, &Denom)>, ContractError>>()?;
+    assert_route_chains(denom, &hops, &base_denom)?;
+
+    assert_route_swaps(denom, decimals, route, |source, amount| {
+        quote(deps, source, amount)
+    })?;
+    Ok(())
+}
+
 pub fn query_fiat_price_for_denom(
     deps: Deps<KujiraQuery>,
+    env: Env,
     fiat: FiatCurrency,
     denom: Denom,
 ) -> StdResult<DenomFiatPrice> {
-    let kq = KujiraQuerier::new(&deps.querier);
-    let atom_usd_price = kq.query_exchange_rate(BASE_ORACLE_DENOM).unwrap();
-    let amount = Uint128::new(1_000_000u128);
-    let denom_str = denom_to_string(&denom.clone());
-    let denom_price_route = &DENOM_PRICE_ROUTE
-        .load(deps.storage, denom_str.as_str())
-        .unwrap();
+    let denom_usd = query_denom_usd_price(deps, env.block.time.seconds(), &denom)?;
+    let fiat_price = load_fiat_price(deps, &env, &fiat)?;
+
+    Ok(DenomFiatPrice {
+        denom: denom.clone(),
+        fiat: fiat.clone(),
+        price: to_denom_fiat_price(Uint256::from(fiat_price.usd_price), denom_usd),
+        decimals: load_denom_metadata(deps.storage, &denom)?.decimals,
+    })
+}
+
+// Prices the denom in each fiat, querying its route only once. Without `fiats`,
+// it's priced in USD and every stored fiat. A fiat whose price can't be used is
+// reported with its error, other failures fail the query.
+fn query_prices(
+    deps: Deps<KujiraQuery>,
+    env: Env,
+    denom: Denom,
+    fiats: Option<Vec<FiatCurrency>>,
+) -> StdResult<Vec<DenomFiatPriceResponse>> {
+    let denom_usd = query_denom_usd_price(deps, env.block.time.seconds(), &denom)?;
+    let decimals = load_denom_metadata(deps.storage, &denom)?.decimals;
+    let fiats = match fiats {
+        Some(fiats) => fiats,
+        None => {
+            let mut fiats = vec![FiatCurrency::USD];
+            for item in FIAT_PRICE.range(deps.storage, None, None, Order::Ascending) {
+                let (_, fiat_price) = item?;
+                if fiat_price.currency != FiatCurrency::USD {
+                    fiats.push(fiat_price.currency);
+                }
+            }
+            fiats
+        }
+    };
+
+    fiats
+        .into_iter()
+        .map(|fiat| match load_fiat_price(deps, &env, &fiat) {
+            Ok(fiat_price) => Ok(DenomFiatPriceResponse {
+                denom: denom.clone(),
+                fiat,
+                price: to_denom_fiat_price(Uint256::from(fiat_price.usd_price), denom_usd),
+                decimals,
+                updated_at: fiat_price.updated_at,
+                error: None,
+            }),
+            Err(e) => match price_query_error_payload(&e) {
+                Some(error) => Ok(DenomFiatPriceResponse {
+                    denom: denom.clone(),
+                    fiat,
+                    price: Uint256::zero(),
+                    decimals,
+                    updated_at: 0,
+                    error: Some(error),
+                }),
+                None => Err(e),
+            },
+        })
+        .collect()
+}
+
+fn query_all_fiat_prices(
+    deps: Deps<KujiraQuery>,
+    limit: u32,
+    last: Option<FiatCurrency>,
+) -> StdResult<Vec<CurrencyPrice>> {
+    let limit = validate_min_max_items_per_page(limit) as usize;
+    let last = last.map(|fiat| fiat.to_string());
+    let range_from = last.as_deref().map(Bound::exclusive);
+    FIAT_PRICE
+        .range(deps.storage, range_from, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, fiat_price)| fiat_price))
+        .collect()
+}
+
+// Spot price of the denom in USD, in 10^12 units per whole denom. Denoms without a USD
+// source are simulated through their route, each hop swapping what the previous one returned.
+fn query_denom_usd_price(deps: Deps<KujiraQuery>, now: u64, denom: &Denom) -> StdResult<Uint256> {
+    let metadata = load_denom_metadata(deps.storage, denom)?;
+    if let Some(source) = usd_source(&metadata) {
+        return Ok(Uint256::from(
+            Uint128::new(PRICE_PRECISION).mul(usd_price(deps, now, source)?),
+        ));
+    }
+    let base_price = route_base_price(deps, &metadata)?;
+    base_to_usd_price(deps, now, base_price)
+}
 
-    // Query the price of the denom in ATOM
-    let denom_atom = denom_price_route
+// Spot price of the denom in the base asset, in 10^12 units per whole denom. Denoms
+// with a USD source are converted at the base oracle rate.
+fn query_denom_base_price(deps: Deps<KujiraQuery>, now: u64, denom: &Denom) -> StdResult<Uint256> {
+    let metadata = load_denom_metadata(deps.storage, denom)?;
+    match usd_source(&metadata) {
+        Some(source) => {
+            let base_oracle = load_base_oracle(deps.storage)?;
+            let precision = Uint128::new(PRICE_PRECISION);
+            let denom_usd = Uint256::from(precision.mul(usd_price(deps, now, source)?));
+            let base_usd = Uint256::from(precision.mul(usd_price(deps, now, &base_oracle.source)?));
+            Ok(denom_usd
+                .mul(Uint256::from(PRICE_PRECISION))
+                .mul(Uint256::from(10u128.pow(base_oracle.decimals as u32)))
+                .checked_div(base_usd)?)
+        }
+        None => route_base_price(deps, &metadata),
+    }
+}
+
+// Converts a price in the base asset to USD, both in 10^12 units per whole denom
+fn base_to_usd_price(deps: Deps<KujiraQuery>, now: u64, base_price: Uint256) -> StdResult<Uint256> {
+    let base_oracle = load_base_oracle(deps.storage)?;
+    let base_usd = Uint128::new(PRICE_PRECISION).mul(usd_price(deps, now, &base_oracle.source)?);
+    let base_unit = Uint256::from(10u128.pow(base_oracle.decimals as u32));
+    Ok(base_price
+        .mul(Uint256::from(base_usd))
+        .div(base_unit.mul(Uint256::from(PRICE_PRECISION))))
+}
+
+fn usd_source(metadata: &DenomMetadata) -> Option<&PriceSource> {
+    match metadata.route.as_slice() {
+        [source] if source.hop().is_none() => Some(source),
+        _ => None,
+    }
+}
+
+// Query how much of the base asset one whole denom is worth
+fn route_base_price(deps: Deps<KujiraQuery>, metadata: &DenomMetadata) -> StdResult<Uint256> {
+    let amount = Uint128::new(10u128.pow(metadata.decimals as u32));
+    let base_amount = metadata
+        .route
         .iter()
-        .fold(Uint256::from(1u128), |price, route| {
-            let denom_price_result: SimulationResponse = deps
-                .querier
-                .query_wasm_smart(
-                    route.pool.clone(),
-                    &FinQueryMsg::Simulation {
-                        offer_asset: Asset {
-                            info: AssetInfo::NativeToken {
-                                denom: KujiraDenom::from(denom_to_string(&route.offer_asset)),
-                            },
-                            amount,
-                        },
-                    },
-                )
-                .unwrap();
-            price * denom_price_result.return_amount
-        });
-    let atom_usd = Uint256::from(Uint128::new(1_000_000u128).mul(atom_usd_price.rate));
+        .try_fold(amount, |amount, source| quote(deps, source, amount))?;
+    Ok(Uint256::from(base_amount).mul(Uint256::from(PRICE_PRECISION)))
+}
+
+fn load_denom_metadata(storage: &dyn Storage, denom: &Denom) -> StdResult<DenomMetadata> {
+    may_load_denom_metadata(storage, denom)?.ok_or_else(|| {
+        price_query_error(ContractError::PriceRouteNotFound {
+            denom: denom_to_string(denom),
+        })
+    })
+}
+
+fn may_load_denom_metadata(
+    storage: &dyn Storage,
+    denom: &Denom,
+) -> StdResult<Option<DenomMetadata>> {
+    let denom_str = denom_to_string(denom);
+    if let Some(metadata) = DENOM_METADATA.may_load(storage, denom_str.as_str())? {
+        return Ok(Some(metadata));
+    }
+    match DENOM_PRICE_ROUTE.may_load(storage, denom_str.as_str())? {
+        Some(route) => {
+            // Legacy hops have no ask asset, each one asks for what the next hop offers
+            // and the last one for the base oracle denom
+            let last_ask = load_base_oracle(storage)?.denom.unwrap_or_default();
+            let asks: Vec<Denom> = route
+                .iter()
+                .skip(1)
+                .map(|hop| hop.offer_asset.clone())
+                .chain(std::iter::once(last_ask))
+                .collect();
+            Ok(Some(DenomMetadata {
+                decimals: DEFAULT_DENOM_DECIMALS,
+                route: route
+                    .into_iter()
+                    .zip(asks)
+                    .map(|(hop, ask_asset)| {
+                        if hop.ask_asset == Denom::default() {
+                            PriceSource::from(PriceRoute { ask_asset, ..hop })
+                        } else {
+                            PriceSource::from(hop)
+                        }
+                    })
+                    .collect(),
+            }))
+        }
+        None => Ok(None),
+    }
+}
 
+fn load_base_oracle(storage: &dyn Storage) -> StdResult<BaseOracle> {
+    Ok(BASE_ORACLE.may_load(storage)?.unwrap_or(BaseOracle {
+        source: PriceSource::KujiraOracle {
+            symbol: BASE_ORACLE_DENOM.to_string(),
+        },
+        denom: None,
+        decimals: BASE_ORACLE_DECIMALS,
+    }))
+}
+
+fn load_fiat_price(
+    deps: Deps<KujiraQuery>,
+    env: &Env,
+    fiat: &FiatCurrency,
+) -> StdResult<CurrencyPrice> {
     // If fiat is USD, we don't need to query the price
     let fiat_price = match fiat {
         FiatCurrency::USD => CurrencyPrice {
             currency: FiatCurrency::USD,
             usd_price: Uint128::new(100u128),
-            updated_at: 0,
+            updated_at: env.block.time.seconds(),
         },
-        _ => FIAT_PRICE.load(deps.storage, fiat.to_string().as_str())?,
+        _ => FIAT_PRICE
+            .may_load(deps.storage, fiat.to_string().as_str())?
+            .ok_or_else(|| {
+                price_query_error(ContractError::FiatPriceNotFound { fiat: fiat.clone() })
+            })?,
     };
+    assert_fiat_price_usable(deps, env, &fiat_price)?;
+    Ok(fiat_price)
+}
 
-    // Calculate the price of the denom in fiat
-    let fiat_usd = Uint256::from(fiat_price.usd_price);
+// Calculate the price of the denom in fiat
+fn to_denom_fiat_price(fiat_usd: Uint256, denom_usd: Uint256) -> Uint256 {
     let decimal_places = 1_000_000_000_000u128;
-    let denom_fiat_price = fiat_usd
-        .mul(&atom_usd)
-        .mul(&denom_atom)
-        .div(Uint256::from(decimal_places));
+    fiat_usd.mul(&denom_usd).div(Uint256::from(decimal_places))
+}
+
+fn query_price_at(
+    deps: Deps<KujiraQuery>,
+    env: Env,
+    fiat: FiatCurrency,
+    denom: Denom,
+    time: u64,
+) -> StdResult<DenomFiatPrice> {
+    let fiat_usd = value_at(&fiat_price_samples(deps, &fiat)?, time)
+        .ok_or_else(|| StdError::generic_err(format!("No {} price at {}.", fiat, time)))?;
+    let denom_str = denom_to_string(&denom);
+    let denom_base = value_at(&denom_price_samples(deps, &denom_str)?, time)
+        .ok_or_else(|| StdError::generic_err(format!("No {} price at {}.", denom_str, time)))?;
+    // Only the market leg is historical, the base asset is priced by its oracle
+    let denom_usd = base_to_usd_price(deps, env.block.time.seconds(), denom_base)?;
+
     Ok(DenomFiatPrice {
-        denom: denom.clone(),
-        fiat: fiat.clone(),
-        price: denom_fiat_price,
+        decimals: load_denom_metadata(deps.storage, &denom)?.decimals,
+        denom,
+        fiat,
+        price: to_denom_fiat_price(fiat_usd, denom_usd),
     })
 }
 
+// The current fiat price must still be usable, the averages only smooth the samples
+fn query_twap(
+    deps: Deps<KujiraQuery>,
+    env: Env,
+    fiat: FiatCurrency,
+    denom: Denom,
+    window: u64,
+) -> StdResult<DenomFiatPrice> {
+    load_fiat_price(deps, &env, &fiat)?;
+    let end = env.block.time.seconds();
+    let start = end.saturating_sub(window);
+    let fiat_usd = time_weighted_average(&fiat_price_samples(deps, &fiat)?, start, end)
+        .ok_or_else(|| StdError::generic_err(format!("No {} price samples.", fiat)))?;
+    let denom_str = denom_to_string(&denom);
+    let denom_samples = denom_price_samples(deps, &denom_str)?;
+    // A window with no fresh denom sample would average a price the market left behind
+    if let Some((updated_at, _)) = denom_samples.last() {
+        if end.saturating_sub(*updated_at) > window {
+            return Err(price_query_error(ContractError::StaleDenomPrice {
+                denom: denom_str,
+                updated_at: *updated_at,
+                max_age: window,
+            }));
+        }
+    }
+    let denom_base = time_weighted_average(&denom_samples, start, end)
+        .ok_or_else(|| StdError::generic_err(format!("No {} price samples.", denom_str)))?;
+    let denom_usd = base_to_usd_price(deps, end, denom_base)?;
+
+    Ok(DenomFiatPrice {
+        decimals: load_denom_metadata(deps.storage, &denom)?.decimals,
+        denom,
+        fiat,
+        price: to_denom_fiat_price(fiat_usd, denom_usd),
+    })
+}
+
+// (timestamp, usd price) samples sorted by time. Prices set before the history existed
+// are used as a single sample.
+fn fiat_price_samples(
+    deps: Deps<KujiraQuery>,
+    fiat: &FiatCurrency,
+) -> StdResult<Vec<(u64, Uint256)>> {
+    if fiat == &FiatCurrency::USD {
+        return Ok(vec![(0, Uint256::from(100u128))]);
+    }
+    let fiat_str = fiat.to_string();
+    let mut samples: Vec<(u64, Uint256)> = FIAT_PRICE_HISTORY
+        .prefix(fiat_str.as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, price)| (price.updated_at, Uint256::from(price.usd_price))))
+        .collect::<StdResult<_>>()?;
+    if samples.is_empty() {
+        if let Some(price) = FIAT_PRICE.may_load(deps.storage, fiat_str.as_str())? {
+            samples.push((price.updated_at, Uint256::from(price.usd_price)));
+        }
+    }
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+    Ok(samples)
+}
+
+fn denom_price_samples(deps: Deps<KujiraQuery>, denom: &str) -> StdResult<Vec<(u64, Uint256)>> {
+    let mut samples: Vec<(u64, Uint256)> = DENOM_PRICE_HISTORY
+        .prefix(denom)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, sample)| (sample.timestamp, sample.base_price)))
+        .collect::<StdResult<_>>()?;
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+    Ok(samples)
+}
+
+fn value_at(samples: &[(u64, Uint256)], time: u64) -> Option<Uint256> {
+    samples
+        .iter()
+        .rev()
+        .find(|(timestamp, _)| *timestamp <= time)
+        .map(|(_, value)| *value)
+}
+
+// Each sample is weighted by how long it was the latest one inside [start, end]
+pub(crate) fn time_weighted_average(
+    samples: &[(u64, Uint256)],
+    start: u64,
+    end: u64,
+) -> Option<Uint256> {
+    let mut weighted_sum = Uint256::zero();
+    let mut total_duration = 0u64;
+    for (i, (timestamp, value)) in samples.iter().enumerate() {
+        let from = (*timestamp).max(start);
+        let to = samples
+            .get(i + 1)
+            .map_or(end, |(next_timestamp, _)| *next_timestamp)
+            .min(end);
+        if to > from {
+            weighted_sum += *value * Uint256::from(to - from);
+            total_duration += to - from;
+        }
+    }
+    if total_duration == 0 {
+        // All the samples are at or after the end of the window
+        return value_at(samples, end);
+    }
+    Some(weighted_sum / Uint256::from(total_duration))
+}
+
+fn query_price_providers(deps: Deps<KujiraQuery>) -> StdResult<Vec<Addr>> {
+    PRICE_PROVIDERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn query_price_submissions(
+    deps: Deps<KujiraQuery>,
+    fiat: FiatCurrency,
+) -> StdResult<Vec<PriceSubmission>> {
+    PRICE_SUBMISSIONS
+        .prefix(fiat.to_string().as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(provider, price)| PriceSubmission { provider, price }))
+        .collect()
+}
+
+fn assert_fiat_price_usable(
+    deps: Deps<KujiraQuery>,
+    env: &Env,
+    fiat_price: &CurrencyPrice,
+) -> StdResult<()> {
+    let fiat = fiat_price.currency.clone();
+    if fiat == FiatCurrency::USD {
+        return Ok(());
+    }
+    if PENDING_FIAT_PRICE.has(deps.storage, fiat.to_string().as_str()) {
+        return Err(price_query_error(ContractError::FiatPriceHalted { fiat }));
+    }
+    let max_age = FIAT_PRICE_MAX_AGE.may_load(deps.storage, fiat.to_string().as_str())?;
+    match max_age {
+        Some(max_age) if fiat_price.is_stale(env.block.time.seconds(), max_age) => {
+            Err(price_query_error(ContractError::StaleFiatPrice {
+                fiat,
+                updated_at: fiat_price.updated_at,
+                max_age,
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<KujiraQuery>,
     _env: Env,
-    _msg: MigrateMsg,
+    msg: MigrateMsg,
 ) -> Result<Response<KujiraMsg>, ContractError> {
     let previous_contract_version = get_contract_version(deps.storage).unwrap();
 
@@ -203,6 +1185,22 @@ pub fn migrate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
     // If the structure of the data in storage changes, we must treat it here
 
+    // Routes must end in the base oracle denom, contracts stored before it existed don't have it
+    let base_oracle = load_base_oracle(deps.storage).unwrap();
+    if base_oracle.denom.is_none() {
+        let base_oracle_denom = msg
+            .base_oracle_denom
+            .ok_or(ContractError::InvalidParameter {
+                parameter: "base_oracle_denom".to_string(),
+                message: Some("The base oracle denom must be set on migrate.".to_string()),
+            })?;
+        let base_oracle = BaseOracle {
+            denom: Some(base_oracle_denom),
+            ..base_oracle
+        };
+        BASE_ORACLE.save(deps.storage, &base_oracle).unwrap();
+    }
+
     Ok(Response::default()
         .add_attribute("previous_version", previous_contract_version.version)
         .add_attribute("new_version", CONTRACT_VERSION)
@@ -1,4 +1,5 @@
 pub mod contract;
+pub mod sources;
 
 #[cfg(test)]
 mod test;
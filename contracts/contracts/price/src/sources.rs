@@ -0,0 +1,103 @@
+use cosmwasm_std::{Addr, Decimal, Deps, StdError, StdResult, Uint128};
+use localmoney_protocol::denom_utils::denom_to_string;
+use localmoney_protocol::errors::ContractError;
+use localmoney_protocol::kujira::asset::{Asset, AssetInfo};
+use localmoney_protocol::kujira::denom::Denom as KujiraDenom;
+use localmoney_protocol::kujira::querier::KujiraQuerier;
+use localmoney_protocol::kujira::query::KujiraQuery;
+use localmoney_protocol::price::{
+    price_query_error, simulate_swap, PriceSource, PUSHED_PRICES, PUSHED_PRICE_MAX_AGE,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Astroport and other cw-pair style pools answer the simulation with a Uint128 return amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum CwPairQueryMsg {
+    Simulation { offer_asset: Asset },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct CwPairSimulationResponse {
+    return_amount: Uint128,
+    spread_amount: Uint128,
+    commission_amount: Uint128,
+}
+
+/// Amount of the ask asset a swap hop returns for `amount` of its offer asset.
+pub fn quote(deps: Deps<KujiraQuery>, source: &PriceSource, amount: Uint128) -> StdResult<Uint128> {
+    match source {
+        PriceSource::Fin {
+            pool, offer_asset, ..
+        } => simulate_swap(&deps.querier, pool, offer_asset, amount)
+            .map_err(|e| pool_simulation_failed(pool, e)),
+        PriceSource::CwPair {
+            pool, offer_asset, ..
+        } => {
+            let simulation: CwPairSimulationResponse = deps
+                .querier
+                .query_wasm_smart(
+                    pool.to_string(),
+                    &CwPairQueryMsg::Simulation {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: KujiraDenom::from(denom_to_string(offer_asset)),
+                            },
+                            amount,
+                        },
+                    },
+                )
+                .map_err(|e| pool_simulation_failed(pool, e))?;
+            Ok(simulation.return_amount)
+        }
+        _ => Err(StdError::generic_err(format!(
+            "{} is not a swap hop.",
+            source
+        ))),
+    }
+}
+
+/// USD price of a whole unit of the asset priced by a USD source.
+pub fn usd_price(deps: Deps<KujiraQuery>, now: u64, source: &PriceSource) -> StdResult<Decimal> {
+    match source {
+        PriceSource::Fixed { usd_price } => Ok(*usd_price),
+        PriceSource::AdminPushed { symbol } => {
+            let pushed_price = PUSHED_PRICES
+                .load(deps.storage, symbol.as_str())
+                .map_err(|e| oracle_unavailable(symbol, e))?;
+            match PUSHED_PRICE_MAX_AGE.may_load(deps.storage, symbol.as_str())? {
+                Some(max_age) if pushed_price.is_stale(now, max_age) => {
+                    Err(price_query_error(ContractError::StalePushedPrice {
+                        symbol: symbol.clone(),
+                        updated_at: pushed_price.updated_at,
+                        max_age,
+                    }))
+                }
+                _ => Ok(pushed_price.usd_price),
+            }
+        }
+        PriceSource::KujiraOracle { symbol } => KujiraQuerier::new(&deps.querier)
+            .query_exchange_rate(symbol.as_str())
+            .map(|exchange_rate| exchange_rate.rate)
+            .map_err(|e| oracle_unavailable(symbol, e)),
+        _ => Err(StdError::generic_err(format!(
+            "{} is not a USD price source.",
+            source
+        ))),
+    }
+}
+
+fn pool_simulation_failed(pool: &Addr, error: StdError) -> StdError {
+    price_query_error(ContractError::PoolSimulationFailed {
+        pool: pool.to_string(),
+        message: error.to_string(),
+    })
+}
+
+fn oracle_unavailable(symbol: &str, error: StdError) -> StdError {
+    price_query_error(ContractError::OracleUnavailable {
+        symbol: symbol.to_string(),
+        message: error.to_string(),
+    })
+}
@@ -1,6 +1,148 @@
-use cosmwasm_std::{Uint128, Uint256};
+use std::marker::PhantomData;
+
+use crate::contract::{
+    execute, instantiate, median, query, time_weighted_average, within_deviation,
+};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{from_binary, Addr, Decimal, Env, OwnedDeps, Uint128, Uint256};
+use cw20::Denom;
+use localmoney_protocol::currencies::FiatCurrency;
+use localmoney_protocol::errors::ContractError;
+use localmoney_protocol::hub::{Admin, HubConfig, HubState, PauseStatus, VersionedConfig};
+use localmoney_protocol::kujira::query::KujiraQuery;
+use localmoney_protocol::price::{
+    price_query_error_payload, BaseOracle, CurrencyPrice, DenomFiatPrice, DenomFiatPriceResponse,
+    DenomMetadata, ExecuteMsg, PendingPrice, PriceAggregation, PriceQueryError, PriceSource,
+    QueryMsg,
+};
+use localmoney_protocol::profile::InstantiateMsg;
 use localmoney_protocol::trade::calc_denom_fiat_price;
 
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier<KujiraQuery>, KujiraQuery>;
+
+// A price contract registered on the hub, the tests only use sources that don't query the oracle
+fn setup() -> MockDeps {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockQuerier::<KujiraQuery>::new(&[]),
+        custom_query_type: PhantomData,
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+    let register_hub = ExecuteMsg::RegisterHub {};
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        register_hub,
+    )
+    .unwrap();
+    let config = VersionedConfig {
+        version: 1,
+        height: 0,
+        time: 0,
+        config: HubConfig {
+            offer_addr: Addr::unchecked("offer"),
+            trade_addr: Addr::unchecked("trade"),
+            profile_addr: Addr::unchecked("profile"),
+            price_addr: Addr::unchecked("price"),
+            price_provider_addr: Addr::unchecked("price_provider"),
+            local_market_addr: Addr::unchecked("local_market"),
+            local_denom: Denom::Native("factory/local".to_string()),
+            chain_fee_collector_addr: Addr::unchecked("chain_fee_collector"),
+            warchest_addr: Addr::unchecked("warchest"),
+            active_offers_limit: 4,
+            active_trades_limit: 20,
+            arbitration_fee_pct: Decimal::percent(1),
+            burn_fee_pct: Decimal::permille(2),
+            chain_fee_pct: Decimal::permille(3),
+            warchest_fee_pct: Decimal::permille(5),
+            trade_expiration_timer: 1200,
+            trade_dispute_timer: 3600,
+            trade_limit_min: 1,
+            trade_limit_max: 500,
+            config_timelock: 0,
+            trade_price_twap_window: 0,
+            fiat_trade_limits: vec![],
+            denom_trade_limits: vec![],
+        },
+    };
+    let update_config = ExecuteMsg::UpdateHubConfig(Box::new(config));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        update_config,
+    )
+    .unwrap();
+    let state = HubState {
+        version: 1,
+        admin: Admin {
+            addr: Addr::unchecked("admin"),
+        },
+        pause_status: PauseStatus::default(),
+        reputation_tiers: vec![],
+        moderator: None,
+        attesters: vec![],
+    };
+    let update_state = ExecuteMsg::UpdateHubState(Box::new(state));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        update_state,
+    )
+    .unwrap();
+    deps
+}
+
+fn update_prices(
+    deps: &mut MockDeps,
+    env: Env,
+    provider: &str,
+    fiat: FiatCurrency,
+    usd_price: u128,
+) -> Result<Vec<(String, String)>, ContractError> {
+    let prices = vec![CurrencyPrice {
+        currency: fiat,
+        usd_price: Uint128::new(usd_price),
+        updated_at: env.block.time.seconds(),
+    }];
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(provider, &[]),
+        ExecuteMsg::UpdatePrices(prices),
+    )?;
+    Ok(res
+        .attributes
+        .into_iter()
+        .map(|attr| (attr.key, attr.value))
+        .collect())
+}
+
+fn register_denom(deps: &mut MockDeps, env: Env, denom: &str, route: Vec<PriceSource>) {
+    let msg = ExecuteMsg::RegisterDenomMetadata {
+        denom: Denom::Native(denom.to_string()),
+        metadata: DenomMetadata { decimals: 6, route },
+    };
+    execute(deps.as_mut(), env, mock_info("admin", &[]), msg).unwrap();
+}
+
+fn query_fiat_prices(deps: &MockDeps) -> Vec<CurrencyPrice> {
+    let msg = QueryMsg::AllFiatPrices {
+        limit: 10,
+        last: None,
+    };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
 #[test]
 fn test() {
     let offer_rate: Uint128 = Uint128::new(199u128);
@@ -8,3 +150,362 @@ fn test() {
     let denom_final_price = calc_denom_fiat_price(offer_rate, denom_fiat_price);
     assert_eq!(denom_final_price, Uint256::from_u128(19u128));
 }
+
+#[test]
+fn median_of_odd_and_even_counts() {
+    let prices = |values: &[u128]| values.iter().map(|v| Uint128::new(*v)).collect();
+    assert_eq!(median(prices(&[30, 10, 20])), Uint128::new(20));
+    assert_eq!(median(prices(&[40, 10, 30, 20])), Uint128::new(25));
+    assert_eq!(median(prices(&[7])), Uint128::new(7));
+}
+
+#[test]
+fn prices_too_far_from_the_median_are_dropped() {
+    let prices = vec![
+        Uint128::new(100),
+        Uint128::new(102),
+        Uint128::new(98),
+        Uint128::new(150),
+    ];
+    let kept = within_deviation(prices, Decimal::percent(5));
+    assert_eq!(
+        kept,
+        vec![Uint128::new(100), Uint128::new(102), Uint128::new(98)]
+    );
+
+    // Nothing can be compared to a zero median
+    let prices = vec![Uint128::zero(), Uint128::zero(), Uint128::new(5)];
+    assert_eq!(
+        within_deviation(prices.clone(), Decimal::percent(5)),
+        prices
+    );
+}
+
+#[test]
+fn samples_are_weighted_by_their_duration() {
+    let samples = vec![(0, Uint256::from_u128(100)), (10, Uint256::from_u128(200))];
+    assert_eq!(
+        time_weighted_average(&samples, 0, 20),
+        Some(Uint256::from_u128(150))
+    );
+    // (5 * 100 + 10 * 200) / 15
+    assert_eq!(
+        time_weighted_average(&samples, 5, 20),
+        Some(Uint256::from_u128(166))
+    );
+    // The last sample before the window counts from its start
+    assert_eq!(
+        time_weighted_average(&samples, 15, 20),
+        Some(Uint256::from_u128(200))
+    );
+}
+
+#[test]
+fn samples_at_the_end_of_the_window_are_used_as_is() {
+    let samples = vec![(20, Uint256::from_u128(7))];
+    assert_eq!(
+        time_weighted_average(&samples, 0, 20),
+        Some(Uint256::from_u128(7))
+    );
+    let samples = vec![(30, Uint256::from_u128(7))];
+    assert_eq!(time_weighted_average(&samples, 0, 20), None);
+}
+
+#[test]
+fn provider_prices_are_aggregated_once_the_quorum_is_reached() {
+    let mut deps = setup();
+    for provider in ["provider1", "provider2", "provider3"] {
+        let msg = ExecuteMsg::UpdatePriceProvider {
+            provider: Addr::unchecked(provider),
+            enabled: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    }
+    let aggregation = PriceAggregation {
+        quorum: 2,
+        max_deviation: Some(Decimal::percent(10)),
+        max_submission_age: 600,
+    };
+    let msg = ExecuteMsg::UpdatePriceAggregation(aggregation);
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+    // Only the hub price provider and the registered providers can submit prices
+    let err = update_prices(&mut deps, mock_env(), "stranger", FiatCurrency::BRL, 500);
+    assert!(matches!(
+        err,
+        Err(ContractError::UnauthorizedPriceProvider { .. })
+    ));
+
+    let attrs = update_prices(&mut deps, mock_env(), "provider1", FiatCurrency::BRL, 500).unwrap();
+    assert!(attrs.contains(&("quorum_pending".to_string(), "BRL".to_string())));
+    assert!(query_fiat_prices(&deps).is_empty());
+
+    // The outlier is dropped and the quorum is still reached by the other two
+    update_prices(&mut deps, mock_env(), "provider2", FiatCurrency::BRL, 900).unwrap();
+    let attrs = update_prices(&mut deps, mock_env(), "provider3", FiatCurrency::BRL, 520).unwrap();
+    assert!(attrs.contains(&("aggregated_usd_price".to_string(), "510".to_string())));
+    assert_eq!(query_fiat_prices(&deps)[0].usd_price, Uint128::new(510));
+
+    // Stale submissions stop counting towards the quorum
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(601);
+    let attrs = update_prices(&mut deps, env, "provider1", FiatCurrency::BRL, 530).unwrap();
+    assert!(attrs.contains(&("quorum_pending".to_string(), "BRL".to_string())));
+    assert_eq!(query_fiat_prices(&deps)[0].usd_price, Uint128::new(510));
+}
+
+#[test]
+fn price_moves_over_the_max_change_wait_for_a_confirmation() {
+    let mut deps = setup();
+    let ukuji = PriceSource::Fixed {
+        usd_price: Decimal::one(),
+    };
+    register_denom(&mut deps, mock_env(), "ukuji", vec![ukuji]);
+    let msg = ExecuteMsg::UpdateFiatMaxPriceChange {
+        fiat: FiatCurrency::BRL,
+        max_change: Some(Decimal::percent(10)),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+    // The first price has nothing to be compared to
+    update_prices(
+        &mut deps,
+        mock_env(),
+        "price_provider",
+        FiatCurrency::BRL,
+        500,
+    )
+    .unwrap();
+    let attrs = update_prices(
+        &mut deps,
+        mock_env(),
+        "price_provider",
+        FiatCurrency::BRL,
+        600,
+    )
+    .unwrap();
+    assert!(attrs.contains(&("halted".to_string(), "BRL".to_string())));
+    let msg = QueryMsg::PendingPrice {
+        fiat: FiatCurrency::BRL,
+    };
+    let pending: Option<PendingPrice> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    let pending = pending.unwrap();
+    assert_eq!(pending.price.usd_price, Uint128::new(600));
+    assert_eq!(pending.previous_usd_price, Uint128::new(500));
+
+    // The denom can't be priced in the halted fiat meanwhile
+    let msg = QueryMsg::Prices {
+        denom: Denom::Native("ukuji".to_string()),
+        fiats: Some(vec![FiatCurrency::BRL]),
+    };
+    let prices: Vec<DenomFiatPriceResponse> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        prices[0].error,
+        Some(PriceQueryError::FiatPriceHalted {
+            fiat: FiatCurrency::BRL
+        })
+    );
+
+    // The provider who proposed the price can't confirm it
+    let msg = ExecuteMsg::ConfirmPendingPrice {
+        fiat: FiatCurrency::BRL,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("price_provider", &[]),
+        msg.clone(),
+    );
+    assert!(matches!(
+        err,
+        Err(ContractError::UnauthorizedPriceProvider { .. })
+    ));
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    assert_eq!(query_fiat_prices(&deps)[0].usd_price, Uint128::new(600));
+
+    // A rejected price leaves the last one in place
+    update_prices(
+        &mut deps,
+        mock_env(),
+        "price_provider",
+        FiatCurrency::BRL,
+        800,
+    )
+    .unwrap();
+    let msg = ExecuteMsg::RejectPendingPrice {
+        fiat: FiatCurrency::BRL,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(query_fiat_prices(&deps)[0].usd_price, Uint128::new(600));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+    assert!(matches!(err, Err(ContractError::NoPendingPrice { .. })));
+}
+
+#[test]
+fn denom_samples_price_the_past_and_the_twap() {
+    let mut deps = setup();
+    let base_oracle = BaseOracle {
+        source: PriceSource::Fixed {
+            usd_price: Decimal::from_ratio(10u128, 1u128),
+        },
+        denom: Some(Denom::Native("uatom".to_string())),
+        decimals: 6,
+    };
+    let msg = ExecuteMsg::UpdateBaseOracle(base_oracle);
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    update_prices(
+        &mut deps,
+        mock_env(),
+        "price_provider",
+        FiatCurrency::BRL,
+        500,
+    )
+    .unwrap();
+    let start = mock_env().block.time.seconds();
+    let ukuji = Denom::Native("ukuji".to_string());
+    let sample_ukuji = ExecuteMsg::SampleDenomPrices {
+        denoms: vec![ukuji.clone()],
+    };
+
+    // Only providers can sample
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        sample_ukuji.clone(),
+    );
+    assert!(matches!(
+        err,
+        Err(ContractError::UnauthorizedPriceProvider { .. })
+    ));
+
+    // The denom is worth 0.1 ATOM, then 0.2 ATOM after 100 seconds
+    let mut env = mock_env();
+    for usd_price in [1u128, 2u128] {
+        let source = PriceSource::Fixed {
+            usd_price: Decimal::from_ratio(usd_price, 1u128),
+        };
+        register_denom(&mut deps, env.clone(), "ukuji", vec![source]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("price_provider", &[]),
+            sample_ukuji.clone(),
+        )
+        .unwrap();
+        let base_price = Uint256::from(usd_price * 100_000_000_000_000_000);
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "base_price" && attr.value == base_price.to_string()));
+        env.block.time = env.block.time.plus_seconds(100);
+    }
+
+    let price_at = |time: u64| {
+        let msg = QueryMsg::PriceAt {
+            fiat: FiatCurrency::BRL,
+            denom: ukuji.clone(),
+            time,
+        };
+        let price: DenomFiatPrice =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        price.price
+    };
+    assert_eq!(price_at(start + 50), Uint256::from(500u128));
+    assert_eq!(price_at(start + 100), Uint256::from(1000u128));
+
+    // Each sample weighs for the half of the window it was the latest
+    let twap = |env: Env| {
+        let msg = QueryMsg::Twap {
+            fiat: FiatCurrency::BRL,
+            denom: ukuji.clone(),
+            window: 200,
+        };
+        query(deps.as_ref(), env, msg).map(|res| from_binary::<DenomFiatPrice>(&res).unwrap())
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    assert_eq!(twap(env.clone()).unwrap().price, Uint256::from(750u128));
+
+    // A window without any recent sample is stale
+    env.block.time = env.block.time.plus_seconds(200);
+    let err = twap(env).unwrap_err();
+    assert!(matches!(
+        price_query_error_payload(&err),
+        Some(PriceQueryError::StaleDenomPrice { .. })
+    ));
+}
+
+#[test]
+fn admin_pushed_prices_price_denoms_until_they_are_stale() {
+    let mut deps = setup();
+    let push_kuji = |usd_price: Decimal| ExecuteMsg::PushPrice {
+        symbol: "KUJI".to_string(),
+        usd_price,
+    };
+    let kuji = PriceSource::AdminPushed {
+        symbol: "KUJI".to_string(),
+    };
+
+    // The route is dry-run, so the price must be pushed first
+    let msg = ExecuteMsg::RegisterDenomMetadata {
+        denom: Denom::Native("ukuji".to_string()),
+        metadata: DenomMetadata {
+            decimals: 6,
+            route: vec![kuji.clone()],
+        },
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+    assert!(matches!(err, Err(ContractError::InvalidRoute { .. })));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        push_kuji(Decimal::one()),
+    );
+    assert!(matches!(err, Err(ContractError::Unauthorized { .. })));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        push_kuji(Decimal::zero()),
+    );
+    assert!(matches!(err, Err(ContractError::InvalidPriceForDenom {})));
+    let msg = push_kuji(Decimal::from_ratio(2u128, 1u128));
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    register_denom(&mut deps, mock_env(), "ukuji", vec![kuji]);
+    let msg = ExecuteMsg::UpdatePushedPriceMaxAge {
+        symbol: "KUJI".to_string(),
+        max_age: Some(60),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+    let query_price = |env: Env| {
+        let msg = QueryMsg::Price {
+            fiat: FiatCurrency::USD,
+            denom: Denom::Native("ukuji".to_string()),
+        };
+        query(deps.as_ref(), env, msg).map(|res| from_binary::<DenomFiatPrice>(&res).unwrap())
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60);
+    assert_eq!(
+        query_price(env.clone()).unwrap().price,
+        Uint256::from(200u128)
+    );
+    env.block.time = env.block.time.plus_seconds(1);
+    let err = query_price(env).unwrap_err();
+    assert!(matches!(
+        price_query_error_payload(&err),
+        Some(PriceQueryError::StalePushedPrice { .. })
+    ));
+}
@@ -1,16 +1,30 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage,
+    Uint128, Uint256,
+};
 use cw2::{get_contract_version, set_contract_version};
+use cw20::Denom;
+use cw_storage_plus::Bound;
+use localmoney_protocol::constants::BLOCKED_USERS_LIMIT;
+use localmoney_protocol::denom_utils::denom_to_string;
 use localmoney_protocol::errors::ContractError;
 use localmoney_protocol::errors::ContractError::HubAlreadyRegistered;
 use localmoney_protocol::guards::{
-    assert_migration_parameters, assert_multiple_ownership, assert_ownership,
+    assert_commitment_valid, assert_contact_valid, assert_encryption_key_valid,
+    assert_migration_parameters, assert_multiple_ownership, assert_ownership, assert_review_valid,
+    assert_suspension_valid, validate_min_max_items_per_page,
+};
+use localmoney_protocol::hub::{HubState, ProtocolOperation, VersionedConfig};
+use localmoney_protocol::hub_utils::{
+    assert_not_paused, get_attester, get_hub_admin, get_hub_config, get_moderator,
+    register_hub_internal, update_hub_config_internal, update_hub_state_internal,
 };
-use localmoney_protocol::hub_utils::{get_hub_config, register_hub_internal};
-use localmoney_protocol::offer::OfferState;
+use localmoney_protocol::offer::{pause_offers_by_owner_msg, OfferState};
 use localmoney_protocol::profile::{
-    ExecuteMsg, InstantiateMsg, MigrateMsg, ProfileModel, QueryMsg,
+    profiles, Badge, DisputeEvent, ExecuteMsg, InstantiateMsg, MigrateMsg, Profile, ProfileModel,
+    QueryMsg, Review, Suspension, BLOCKED_USERS, ENCRYPTION_KEYS, REVIEWS,
 };
 use localmoney_protocol::trade::TradeState;
 
@@ -47,11 +61,53 @@ pub fn execute(
             profile_addr,
             trade_state,
         } => update_trades_count(deps, env, info, profile_addr, trade_state),
+        ExecuteMsg::UpdateDisputesCount {
+            profile_addr,
+            dispute_event,
+        } => update_disputes_count(deps, info, profile_addr, dispute_event),
+        ExecuteMsg::AddReview {
+            profile_addr,
+            review,
+        } => add_review(deps, info, profile_addr, review),
+        ExecuteMsg::RotateEncryptionKey { encryption_key } => {
+            rotate_encryption_key(deps, env, info, encryption_key)
+        }
+        ExecuteMsg::BlockUser { addr } => block_user(deps, env, info, addr),
+        ExecuteMsg::UnblockUser { addr } => unblock_user(deps, info, addr),
+        ExecuteMsg::SuspendProfile {
+            profile_addr,
+            reason,
+            expires_at,
+        } => suspend_profile(deps, env, info, profile_addr, reason, expires_at),
+        ExecuteMsg::UnsuspendProfile { profile_addr } => {
+            unsuspend_profile(deps, info, profile_addr)
+        }
+        ExecuteMsg::Attest {
+            profile_addr,
+            badge,
+            expires_at,
+            commitment,
+        } => attest(deps, env, info, profile_addr, badge, expires_at, commitment),
+        ExecuteMsg::RevokeAttestation {
+            profile_addr,
+            badge,
+        } => revoke_attestation(deps, info, profile_addr, badge),
+        ExecuteMsg::UpdateTradedVolume {
+            profile_addr,
+            denom,
+            amount,
+            usd_amount,
+        } => update_traded_volume(deps, info, profile_addr, denom, amount, usd_amount),
         ExecuteMsg::UpdateActiveOffers {
             profile_addr,
             offer_state,
         } => update_active_offers(deps, info, profile_addr, offer_state),
+        ExecuteMsg::ReindexProfiles { start_after, limit } => {
+            reindex_profiles(deps, info, start_after, limit)
+        }
         ExecuteMsg::RegisterHub {} => register_hub(deps, info),
+        ExecuteMsg::UpdateHubConfig(config) => update_hub_config(deps, info, config),
+        ExecuteMsg::UpdateHubState(state) => update_hub_state(deps, info, state),
     }
 }
 
@@ -60,9 +116,16 @@ fn update_profile_contact(
     env: Env,
     info: MessageInfo,
     profile_addr: Addr,
-    contact: String,
-    encryption_key: String,
+    contact: Option<String>,
+    encryption_key: Option<String>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::UpdateProfile)?;
+    if let Some(contact) = &contact {
+        assert_contact_valid(contact)?;
+    }
+    if let Some(encryption_key) = &encryption_key {
+        assert_encryption_key_valid(encryption_key)?;
+    }
     let hub_config = get_hub_config(deps.as_ref());
     let owners = vec![
         profile_addr.clone(),
@@ -73,23 +136,88 @@ fn update_profile_contact(
     assert_multiple_ownership(info.sender, owners).unwrap();
 
     let storage = deps.storage;
-    let mut profile = ProfileModel::query_profile(storage, profile_addr.clone());
+    let mut profile = ProfileModel::query_profile(storage, profile_addr.clone()).unwrap();
     if profile.created_at.eq(&0) {
         let created_at = env.block.time.seconds();
         profile.created_at = created_at
     }
-    profile.contact = Some(contact.clone());
-    profile.encryption_key = Some(encryption_key.clone());
+    // Only the given fields are updated, the others are kept as they are
+    if contact.is_some() {
+        profile.contact = contact;
+    }
+    if let Some(encryption_key) = encryption_key {
+        set_encryption_key(storage, &mut profile, encryption_key);
+    }
     ProfileModel::store(storage, &profile);
 
     let res = Response::new()
         .add_attribute("action", "update_profile")
         .add_attribute("profile_addr", profile_addr.to_string())
-        .add_attribute("contact", contact.to_string())
-        .add_attribute("encryption_pk", encryption_key.to_string());
+        .add_attribute("contact", profile.contact.unwrap_or_default())
+        .add_attribute("encryption_pk", profile.encryption_key.unwrap_or_default())
+        .add_attribute(
+            "encryption_key_version",
+            profile.encryption_key_version.to_string(),
+        );
+    Ok(res)
+}
+
+fn rotate_encryption_key(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    encryption_key: String,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::UpdateProfile)?;
+    assert_encryption_key_valid(&encryption_key)?;
+
+    let mut profile = ProfileModel::query_profile(deps.storage, info.sender.clone()).unwrap();
+    if profile.encryption_key.as_deref() == Some(encryption_key.as_str()) {
+        return Err(ContractError::InvalidParameter {
+            parameter: "encryption_key".to_string(),
+            message: Some("The new encryption key must be different.".to_string()),
+        });
+    }
+    if profile.created_at.eq(&0) {
+        profile.created_at = env.block.time.seconds();
+    }
+    set_encryption_key(deps.storage, &mut profile, encryption_key.clone());
+    ProfileModel::store(deps.storage, &profile);
+
+    let res = Response::new()
+        .add_attribute("action", "rotate_encryption_key")
+        .add_attribute("profile_addr", info.sender.to_string())
+        .add_attribute("encryption_pk", encryption_key)
+        .add_attribute(
+            "encryption_key_version",
+            profile.encryption_key_version.to_string(),
+        );
     Ok(res)
 }
 
+// A new key gets a new version, previous keys are kept for the trades that used them
+fn set_encryption_key(storage: &mut dyn Storage, profile: &mut Profile, encryption_key: String) {
+    let version = profile.encryption_key_version_for(&encryption_key);
+    if version.eq(&profile.encryption_key_version) {
+        return;
+    }
+    // Profiles created before keys were versioned have their key only on the profile
+    if let Some(current_key) = &profile.encryption_key {
+        ENCRYPTION_KEYS
+            .save(
+                storage,
+                (&profile.addr, profile.encryption_key_version),
+                current_key,
+            )
+            .unwrap();
+    }
+    ENCRYPTION_KEYS
+        .save(storage, (&profile.addr, version), &encryption_key)
+        .unwrap();
+    profile.encryption_key = Some(encryption_key);
+    profile.encryption_key_version = version;
+}
+
 pub fn update_trades_count(
     deps: DepsMut,
     env: Env,
@@ -122,6 +250,14 @@ pub fn update_trades_count(
                 });
             }
         }
+        // Canceled and expired trades are only counted, the active trades count follows
+        // the EscrowCanceled and EscrowRefunded states sent along with them
+        TradeState::RequestCanceled => {
+            profile.canceled_trades_count += 1;
+        }
+        TradeState::RequestExpired => {
+            profile.expired_trades_count += 1;
+        }
         TradeState::EscrowCanceled
         | TradeState::EscrowRefunded
         | TradeState::SettledForMaker
@@ -157,6 +293,327 @@ pub fn update_trades_count(
     Ok(res)
 }
 
+pub fn update_disputes_count(
+    deps: DepsMut,
+    info: MessageInfo,
+    profile_addr: Addr,
+    dispute_event: DisputeEvent,
+) -> Result<Response, ContractError> {
+    // Only the trade contract should be able to call this method.
+    let hub_config = get_hub_config(deps.as_ref());
+    assert_ownership(info.sender, hub_config.trade_addr).unwrap();
+
+    let mut profile_model = ProfileModel::from_store(deps.storage, profile_addr.clone()).unwrap();
+    let profile = &mut profile_model.profile;
+    match dispute_event {
+        DisputeEvent::Opened => profile.disputes_opened_count += 1,
+        DisputeEvent::Won => profile.disputes_won_count += 1,
+        DisputeEvent::Lost => profile.disputes_lost_count += 1,
+    }
+    let profile = profile_model.save();
+
+    let res = Response::new()
+        .add_attribute("action", "update_disputes_count")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("dispute_event", dispute_event.to_string())
+        .add_attribute(
+            "disputes_opened_count",
+            profile.disputes_opened_count.to_string(),
+        )
+        .add_attribute("disputes_won_count", profile.disputes_won_count.to_string())
+        .add_attribute(
+            "disputes_lost_count",
+            profile.disputes_lost_count.to_string(),
+        );
+    Ok(res)
+}
+
+fn block_user(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: Addr,
+) -> Result<Response, ContractError> {
+    if addr.eq(&info.sender) {
+        return Err(ContractError::InvalidParameter {
+            parameter: "addr".to_string(),
+            message: Some("Users can not block themselves.".to_string()),
+        });
+    }
+    let blocked_users_count = BLOCKED_USERS
+        .prefix(&info.sender)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if blocked_users_count >= BLOCKED_USERS_LIMIT {
+        return Err(ContractError::BlockedUsersLimitReached {
+            limit: BLOCKED_USERS_LIMIT,
+        });
+    }
+    BLOCKED_USERS
+        .save(
+            deps.storage,
+            (&info.sender, &addr),
+            &env.block.time.seconds(),
+        )
+        .unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "block_user")
+        .add_attribute("blocker", info.sender.to_string())
+        .add_attribute("blocked", addr.to_string());
+    Ok(res)
+}
+
+fn unblock_user(deps: DepsMut, info: MessageInfo, addr: Addr) -> Result<Response, ContractError> {
+    BLOCKED_USERS.remove(deps.storage, (&info.sender, &addr));
+
+    let res = Response::new()
+        .add_attribute("action", "unblock_user")
+        .add_attribute("blocker", info.sender.to_string())
+        .add_attribute("blocked", addr.to_string());
+    Ok(res)
+}
+
+fn suspend_profile(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    profile_addr: Addr,
+    reason: String,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_moderator(deps.as_ref(), info.sender.clone())?;
+    assert_suspension_valid(&reason, expires_at, env.block.time.seconds())?;
+    let hub_config = get_hub_config(deps.as_ref());
+
+    let mut profile = ProfileModel::query_profile(deps.storage, profile_addr.clone()).unwrap();
+    profile.suspension = Some(Suspension {
+        reason: reason.clone(),
+        moderator: info.sender,
+        suspended_at: env.block.time.seconds(),
+        expires_at,
+    });
+    ProfileModel::store(deps.storage, &profile);
+
+    // Funded trades can still be completed, but the profile's offers are no longer listed
+    let pause_offers_msg =
+        pause_offers_by_owner_msg(hub_config.offer_addr.to_string(), profile_addr.clone());
+
+    let res = Response::new()
+        .add_submessage(pause_offers_msg)
+        .add_attribute("action", "suspend_profile")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("reason", reason)
+        .add_attribute(
+            "expires_at",
+            expires_at.map(|e| e.to_string()).unwrap_or_default(),
+        );
+    Ok(res)
+}
+
+fn unsuspend_profile(
+    deps: DepsMut,
+    info: MessageInfo,
+    profile_addr: Addr,
+) -> Result<Response, ContractError> {
+    assert_moderator(deps.as_ref(), info.sender)?;
+
+    let mut profile_model = ProfileModel::from_store(deps.storage, profile_addr.clone()).unwrap();
+    profile_model.profile.suspension = None;
+    profile_model.save();
+
+    let res = Response::new()
+        .add_attribute("action", "unsuspend_profile")
+        .add_attribute("profile_addr", profile_addr.to_string());
+    Ok(res)
+}
+
+// Attesting a badge the profile already has replaces it
+fn attest(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    profile_addr: Addr,
+    badge: String,
+    expires_at: u64,
+    commitment: Option<String>,
+) -> Result<Response, ContractError> {
+    let can_attest = match get_attester(deps.as_ref(), info.sender.clone()) {
+        Some(attester) => attester.can_attest(&badge),
+        None => false,
+    };
+    if !can_attest {
+        return Err(ContractError::UnauthorizedAttester {
+            attester: info.sender,
+            badge,
+        });
+    }
+    let now = env.block.time.seconds();
+    if expires_at <= now {
+        return Err(ContractError::InvalidParameter {
+            parameter: "expires_at".to_string(),
+            message: Some("It must be in the future.".to_string()),
+        });
+    }
+    if let Some(commitment) = &commitment {
+        assert_commitment_valid(commitment)?;
+    }
+
+    let mut profile = ProfileModel::query_profile(deps.storage, profile_addr.clone()).unwrap();
+    if profile.created_at.eq(&0) {
+        profile.created_at = now;
+    }
+    profile.badges.retain(|b| b.name.ne(&badge));
+    profile.badges.push(Badge {
+        name: badge.clone(),
+        attester: info.sender.clone(),
+        commitment,
+        attested_at: now,
+        expires_at,
+    });
+    ProfileModel::store(deps.storage, &profile);
+
+    let res = Response::new()
+        .add_attribute("action", "attest")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("badge", badge)
+        .add_attribute("attester", info.sender.to_string())
+        .add_attribute("expires_at", expires_at.to_string());
+    Ok(res)
+}
+
+// A badge can be revoked by the attester that issued it or by a moderator
+fn revoke_attestation(
+    deps: DepsMut,
+    info: MessageInfo,
+    profile_addr: Addr,
+    badge: String,
+) -> Result<Response, ContractError> {
+    let mut profile = ProfileModel::query_profile(deps.storage, profile_addr.clone()).unwrap();
+    let attester = match profile.badges.iter().find(|b| b.name.eq(&badge)) {
+        Some(b) => b.attester.clone(),
+        None => {
+            return Err(ContractError::BadgeNotFound {
+                profile_addr,
+                badge,
+            })
+        }
+    };
+    if info.sender.ne(&attester) {
+        assert_moderator(deps.as_ref(), info.sender.clone())?;
+    }
+    profile.badges.retain(|b| b.name.ne(&badge));
+    ProfileModel::store(deps.storage, &profile);
+
+    let res = Response::new()
+        .add_attribute("action", "revoke_attestation")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("badge", badge)
+        .add_attribute("revoked_by", info.sender.to_string());
+    Ok(res)
+}
+
+// Profiles are reindexed in pages, the last reindexed address is the cursor of the next page
+fn reindex_profiles(
+    deps: DepsMut,
+    info: MessageInfo,
+    start_after: Option<Addr>,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    assert_ownership(info.sender, get_hub_admin(deps.as_ref()).addr)?;
+
+    let limit = validate_min_max_items_per_page(limit) as usize;
+    let range_from = start_after.map(|addr| Bound::exclusive(addr.to_string()));
+    let stored_profiles: Vec<(String, Profile)> = profiles()
+        .range(deps.storage, range_from, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()
+        .unwrap();
+    for (key, profile) in stored_profiles.iter() {
+        profiles().save(deps.storage, key.clone(), profile).unwrap();
+    }
+
+    let last_addr = stored_profiles
+        .last()
+        .map(|(key, _)| key.clone())
+        .unwrap_or_default();
+    let res = Response::new()
+        .add_attribute("action", "reindex_profiles")
+        .add_attribute("reindexed_profiles", stored_profiles.len().to_string())
+        .add_attribute("last_addr", last_addr);
+    Ok(res)
+}
+
+// The hub admin can always act as a moderator
+fn assert_moderator(deps: Deps, sender: Addr) -> Result<(), ContractError> {
+    let mut moderators = vec![get_hub_admin(deps).addr];
+    if let Some(moderator) = get_moderator(deps) {
+        moderators.push(moderator);
+    }
+    assert_multiple_ownership(sender, moderators)
+}
+
+pub fn update_traded_volume(
+    deps: DepsMut,
+    info: MessageInfo,
+    profile_addr: Addr,
+    denom: Denom,
+    amount: Uint128,
+    usd_amount: Uint256,
+) -> Result<Response, ContractError> {
+    // Only the trade contract should be able to call this method.
+    let hub_config = get_hub_config(deps.as_ref());
+    assert_ownership(info.sender, hub_config.trade_addr).unwrap();
+
+    let mut profile_model = ProfileModel::from_store(deps.storage, profile_addr.clone()).unwrap();
+    profile_model
+        .profile
+        .add_traded_volume(denom.clone(), amount, usd_amount);
+    let profile = profile_model.save();
+
+    let res = Response::new()
+        .add_attribute("action", "update_traded_volume")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("denom", denom_to_string(&denom))
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("usd_traded_volume", profile.usd_traded_volume.to_string());
+    Ok(res)
+}
+
+pub fn add_review(
+    deps: DepsMut,
+    info: MessageInfo,
+    profile_addr: Addr,
+    review: Review,
+) -> Result<Response, ContractError> {
+    // Only the trade contract should be able to call this method.
+    let hub_config = get_hub_config(deps.as_ref());
+    assert_ownership(info.sender, hub_config.trade_addr).unwrap();
+    assert_review_valid(&review)?;
+
+    if REVIEWS.has(deps.storage, (&profile_addr, review.trade_id)) {
+        return Err(ContractError::TradeAlreadyReviewed {
+            trade_id: review.trade_id,
+        });
+    }
+    REVIEWS
+        .save(deps.storage, (&profile_addr, review.trade_id), &review)
+        .unwrap();
+
+    let mut profile_model = ProfileModel::from_store(deps.storage, profile_addr.clone()).unwrap();
+    profile_model.profile.add_rating(review.rating);
+    let profile = profile_model.save();
+
+    let res = Response::new()
+        .add_attribute("action", "add_review")
+        .add_attribute("profile_addr", profile_addr.to_string())
+        .add_attribute("reviewer", review.reviewer.to_string())
+        .add_attribute("trade_id", review.trade_id.to_string())
+        .add_attribute("rating", review.rating.to_string())
+        .add_attribute("average_rating", profile.average_rating.to_string());
+    Ok(res)
+}
+
 pub fn update_active_offers(
     deps: DepsMut,
     info: MessageInfo,
@@ -199,18 +656,65 @@ fn register_hub(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractEr
     register_hub_internal(info.sender, deps.storage, HubAlreadyRegistered {})
 }
 
+fn update_hub_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Box<VersionedConfig>,
+) -> Result<Response, ContractError> {
+    update_hub_config_internal(info.sender, deps.storage, *config)
+}
+
+fn update_hub_state(
+    deps: DepsMut,
+    info: MessageInfo,
+    state: Box<HubState>,
+) -> Result<Response, ContractError> {
+    update_hub_state_internal(info.sender, deps.storage, *state)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let now = env.block.time.seconds();
     match msg {
         QueryMsg::Profile { addr } => {
-            to_binary(&ProfileModel::query_profile(deps.storage, addr.clone()))
+            let mut profile = ProfileModel::query_profile(deps.storage, addr.clone())?;
+            profile.retain_active_badges(now);
+            to_binary(&profile)
+        }
+        QueryMsg::Profiles {
+            order,
+            limit,
+            start_after,
+        } => to_binary(&with_active_badges(
+            ProfileModel::query_profiles(deps, order, limit, start_after)?,
+            now,
+        )),
+        QueryMsg::ProfilesByAddrs { addrs } => to_binary(&with_active_badges(
+            ProfileModel::query_profiles_by_addrs(deps, addrs)?,
+            now,
+        )),
+        QueryMsg::EncryptionKey { addr, version } => {
+            to_binary(&ProfileModel::query_encryption_key(deps, addr, version)?)
         }
-        QueryMsg::Profiles { limit, start_at } => {
-            to_binary(&ProfileModel::query_profiles(deps, env, limit, start_at)?)
+        QueryMsg::BlockedUsers { addr } => {
+            to_binary(&ProfileModel::query_blocked_users(deps, addr)?)
+        }
+        QueryMsg::IsBlockedBetween { addr, counterparty } => to_binary(
+            &ProfileModel::query_is_blocked_between(deps, addr, counterparty)?,
+        ),
+        QueryMsg::Reviews { addr, limit, last } => {
+            to_binary(&ProfileModel::query_reviews(deps, addr, limit, last)?)
         }
     }
 }
 
+fn with_active_badges(mut profiles: Vec<Profile>, now: u64) -> Vec<Profile> {
+    profiles
+        .iter_mut()
+        .for_each(|profile| profile.retain_active_badges(now));
+    profiles
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let previous_contract_version = get_contract_version(deps.storage).unwrap();
@@ -0,0 +1,507 @@
+use crate::contract::{execute, instantiate, query};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{
+    from_binary, Addr, CosmosMsg, Decimal, MemoryStorage, OwnedDeps, Uint128, Uint256, WasmMsg,
+};
+use cw20::Denom;
+use localmoney_protocol::constants::{BLOCKED_USERS_LIMIT, SUSPENSION_REASON_LIMIT};
+use localmoney_protocol::errors::ContractError;
+use localmoney_protocol::hub::{
+    Admin, Attester, HubConfig, HubState, PauseStatus, VersionedConfig,
+};
+use localmoney_protocol::profile::{
+    DisputeEvent, ExecuteMsg, InstantiateMsg, Profile, QueryMsg, Review,
+};
+use localmoney_protocol::trade::TradeState;
+
+type MockDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+// A profile contract registered on the hub, with a profile for the maker
+fn setup() -> MockDeps {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+    let register_hub = ExecuteMsg::RegisterHub {};
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        register_hub,
+    )
+    .unwrap();
+    let config = VersionedConfig {
+        version: 1,
+        height: 0,
+        time: 0,
+        config: HubConfig {
+            offer_addr: Addr::unchecked("offer"),
+            trade_addr: Addr::unchecked("trade"),
+            profile_addr: Addr::unchecked("profile"),
+            price_addr: Addr::unchecked("price"),
+            price_provider_addr: Addr::unchecked("price_provider"),
+            local_market_addr: Addr::unchecked("local_market"),
+            local_denom: Denom::Native("factory/local".to_string()),
+            chain_fee_collector_addr: Addr::unchecked("chain_fee_collector"),
+            warchest_addr: Addr::unchecked("warchest"),
+            active_offers_limit: 4,
+            active_trades_limit: 20,
+            arbitration_fee_pct: Decimal::percent(1),
+            burn_fee_pct: Decimal::permille(2),
+            chain_fee_pct: Decimal::permille(3),
+            warchest_fee_pct: Decimal::permille(5),
+            trade_expiration_timer: 1200,
+            trade_dispute_timer: 3600,
+            trade_limit_min: 1,
+            trade_limit_max: 500,
+            config_timelock: 0,
+            trade_price_twap_window: 0,
+            fiat_trade_limits: vec![],
+            denom_trade_limits: vec![],
+        },
+    };
+    let update_config = ExecuteMsg::UpdateHubConfig(Box::new(config));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        update_config,
+    )
+    .unwrap();
+    let state = HubState {
+        version: 1,
+        admin: Admin {
+            addr: Addr::unchecked("admin"),
+        },
+        pause_status: PauseStatus::default(),
+        reputation_tiers: vec![],
+        moderator: Some(Addr::unchecked("moderator")),
+        attesters: vec![],
+    };
+    let update_state = ExecuteMsg::UpdateHubState(Box::new(state));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        update_state,
+    )
+    .unwrap();
+
+    let update_contact = ExecuteMsg::UpdateContact {
+        profile_addr: Addr::unchecked("maker"),
+        contact: Some("Y29udGFjdA==".to_string()),
+        encryption_key: Some("a2V5".to_string()),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        update_contact,
+    )
+    .unwrap();
+    deps
+}
+
+fn query_profile(deps: &MockDeps, addr: &str) -> Profile {
+    let msg = QueryMsg::Profile {
+        addr: Addr::unchecked(addr),
+    };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn trade_outcomes_are_counted_on_the_profile() {
+    let mut deps = setup();
+    let mut update_trades_count = |trade_state: TradeState| {
+        let msg = ExecuteMsg::UpdateTradesCount {
+            profile_addr: Addr::unchecked("maker"),
+            trade_state,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("trade", &[]), msg).unwrap();
+    };
+    // A canceled accepted request, the trade contract also sends EscrowCanceled
+    update_trades_count(TradeState::RequestCreated);
+    update_trades_count(TradeState::RequestAccepted);
+    update_trades_count(TradeState::RequestCanceled);
+    update_trades_count(TradeState::EscrowCanceled);
+    // An expired funded escrow, refunded along with RequestExpired
+    update_trades_count(TradeState::RequestCreated);
+    update_trades_count(TradeState::EscrowFunded);
+    update_trades_count(TradeState::EscrowRefunded);
+    update_trades_count(TradeState::RequestExpired);
+    // A released trade
+    update_trades_count(TradeState::RequestCreated);
+    update_trades_count(TradeState::EscrowFunded);
+    update_trades_count(TradeState::EscrowReleased);
+
+    let profile = query_profile(&deps, "maker");
+    assert_eq!(profile.requested_trades_count, 3);
+    assert_eq!(profile.active_trades_count, 0);
+    assert_eq!(profile.released_trades_count, 1);
+    assert_eq!(profile.canceled_trades_count, 1);
+    assert_eq!(profile.expired_trades_count, 1);
+
+    for dispute_event in [DisputeEvent::Opened, DisputeEvent::Lost] {
+        let msg = ExecuteMsg::UpdateDisputesCount {
+            profile_addr: Addr::unchecked("maker"),
+            dispute_event,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("trade", &[]), msg).unwrap();
+    }
+    let msg = ExecuteMsg::UpdateTradedVolume {
+        profile_addr: Addr::unchecked("maker"),
+        denom: Denom::Native("ukuji".to_string()),
+        amount: Uint128::new(2_000_000),
+        usd_amount: Uint256::from_u128(3),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("trade", &[]), msg).unwrap();
+
+    let profile = query_profile(&deps, "maker");
+    assert_eq!(profile.disputes_opened_count, 1);
+    assert_eq!(profile.disputes_lost_count, 1);
+    assert_eq!(profile.disputes_won_count, 0);
+    assert_eq!(profile.usd_traded_volume, Uint256::from_u128(3));
+}
+
+#[test]
+fn moderators_suspend_profiles_and_pause_their_offers() {
+    let mut deps = setup();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let suspend = |reason: &str, expires_at: Option<u64>| ExecuteMsg::SuspendProfile {
+        profile_addr: Addr::unchecked("maker"),
+        reason: reason.to_string(),
+        expires_at,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("someone", &[]),
+        suspend("scam", None),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::UnauthorizedMultipleOwnership { .. }
+    ));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("moderator", &[]),
+        suspend("scam", Some(now)),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+    let long_reason = "a".repeat(SUSPENSION_REASON_LIMIT + 1);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("moderator", &[]),
+        suspend(&long_reason, None),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("moderator", &[]),
+        suspend("scam", Some(now + 3600)),
+    )
+    .unwrap();
+    assert!(matches!(
+        &res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) if contract_addr == "offer"
+    ));
+    let profile = query_profile(&deps, "maker");
+    assert!(profile.active_suspension(now).is_some());
+    // The suspension is lifted once it expires
+    assert!(profile.active_suspension(now + 3600).is_none());
+
+    // The hub admin can act as a moderator
+    let unsuspend = ExecuteMsg::UnsuspendProfile {
+        profile_addr: Addr::unchecked("maker"),
+    };
+    execute(deps.as_mut(), env, mock_info("admin", &[]), unsuspend).unwrap();
+    assert_eq!(query_profile(&deps, "maker").suspension, None);
+}
+
+#[test]
+fn reviews_are_added_once_per_trade() {
+    let mut deps = setup();
+    let review = |trade_id: u64, rating: u8| Review {
+        trade_id,
+        reviewer: Addr::unchecked("taker"),
+        rating,
+        comment: Some("Fast and friendly.".to_string()),
+        created_at: 0,
+    };
+    let add_review = |review: Review| ExecuteMsg::AddReview {
+        profile_addr: Addr::unchecked("maker"),
+        review,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trade", &[]),
+        add_review(review(1, 5)),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trade", &[]),
+        add_review(review(2, 4)),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trade", &[]),
+        add_review(review(2, 1)),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::TradeAlreadyReviewed { .. }));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trade", &[]),
+        add_review(review(3, 6)),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ValueOutOfRange { .. }));
+
+    let profile = query_profile(&deps, "maker");
+    assert_eq!(profile.reviews_count, 2);
+    assert_eq!(profile.average_rating, Decimal::from_ratio(9u128, 2u128));
+
+    // The latest reviews come first
+    let msg = QueryMsg::Reviews {
+        addr: Addr::unchecked("maker"),
+        limit: 10,
+        last: None,
+    };
+    let reviews: Vec<Review> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(reviews, vec![review(2, 4), review(1, 5)]);
+    let msg = QueryMsg::Reviews {
+        addr: Addr::unchecked("maker"),
+        limit: 10,
+        last: Some(2),
+    };
+    let reviews: Vec<Review> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(reviews, vec![review(1, 5)]);
+}
+
+#[test]
+fn users_block_their_counterparties() {
+    let mut deps = setup();
+    let block = |addr: String| ExecuteMsg::BlockUser {
+        addr: Addr::unchecked(addr),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        block("maker".to_string()),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        block("taker".to_string()),
+    )
+    .unwrap();
+
+    let is_blocked_between = |deps: &MockDeps, addr: &str, counterparty: &str| -> bool {
+        let msg = QueryMsg::IsBlockedBetween {
+            addr: Addr::unchecked(addr),
+            counterparty: Addr::unchecked(counterparty),
+        };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    // Blocking works both ways
+    assert!(is_blocked_between(&deps, "maker", "taker"));
+    assert!(is_blocked_between(&deps, "taker", "maker"));
+    assert!(!is_blocked_between(&deps, "maker", "someone"));
+
+    let unblock = ExecuteMsg::UnblockUser {
+        addr: Addr::unchecked("taker"),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("maker", &[]), unblock).unwrap();
+    assert!(!is_blocked_between(&deps, "taker", "maker"));
+
+    for i in 0..BLOCKED_USERS_LIMIT {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("maker", &[]),
+            block(format!("user{}", i)),
+        )
+        .unwrap();
+    }
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        block("taker".to_string()),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::BlockedUsersLimitReached { .. }
+    ));
+    let msg = QueryMsg::BlockedUsers {
+        addr: Addr::unchecked("maker"),
+    };
+    let blocked_users: Vec<Addr> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(blocked_users.len(), BLOCKED_USERS_LIMIT);
+}
+
+#[test]
+fn rotated_encryption_keys_are_kept_by_version() {
+    let mut deps = setup();
+    assert_eq!(query_profile(&deps, "maker").encryption_key_version, 1);
+    let rotate = |encryption_key: &str| ExecuteMsg::RotateEncryptionKey {
+        encryption_key: encryption_key.to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        rotate("a2V5"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("maker", &[]),
+        rotate("bmV3a2V5"),
+    )
+    .unwrap();
+
+    let profile = query_profile(&deps, "maker");
+    assert_eq!(profile.encryption_key, Some("bmV3a2V5".to_string()));
+    assert_eq!(profile.encryption_key_version, 2);
+    let encryption_key = |version: Option<u64>| -> Option<String> {
+        let msg = QueryMsg::EncryptionKey {
+            addr: Addr::unchecked("maker"),
+            version,
+        };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    assert_eq!(encryption_key(None), Some("bmV3a2V5".to_string()));
+    assert_eq!(encryption_key(Some(1)), Some("a2V5".to_string()));
+    assert_eq!(encryption_key(Some(3)), None);
+}
+
+#[test]
+fn attesters_grant_the_badges_they_are_registered_for() {
+    let mut deps = setup();
+    let state = HubState {
+        version: 2,
+        admin: Admin {
+            addr: Addr::unchecked("admin"),
+        },
+        pause_status: PauseStatus::default(),
+        reputation_tiers: vec![],
+        moderator: None,
+        attesters: vec![Attester {
+            addr: Addr::unchecked("attester"),
+            badges: vec!["kyc-level-1".to_string()],
+        }],
+    };
+    let update_state = ExecuteMsg::UpdateHubState(Box::new(state));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("hub", &[]),
+        update_state,
+    )
+    .unwrap();
+
+    let now = mock_env().block.time.seconds();
+    let attest = |badge: &str, expires_at: u64| ExecuteMsg::Attest {
+        profile_addr: Addr::unchecked("maker"),
+        badge: badge.to_string(),
+        expires_at,
+        commitment: Some("ab".repeat(32)),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone", &[]),
+        attest("kyc-level-1", now + 3600),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::UnauthorizedAttester { .. }));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attester", &[]),
+        attest("kyc-level-2", now + 3600),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::UnauthorizedAttester { .. }));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attester", &[]),
+        attest("kyc-level-1", now),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidParameter { .. }));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attester", &[]),
+        attest("kyc-level-1", now + 3600),
+    )
+    .unwrap();
+    let profile = query_profile(&deps, "maker");
+    assert!(profile.has_active_badge("kyc-level-1", now));
+    // Expired badges are not listed
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+    let msg = QueryMsg::Profile {
+        addr: Addr::unchecked("maker"),
+    };
+    let profile: Profile = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert!(profile.badges.is_empty());
+
+    // Only the attester that issued the badge or a moderator can revoke it
+    let revoke = ExecuteMsg::RevokeAttestation {
+        profile_addr: Addr::unchecked("maker"),
+        badge: "kyc-level-1".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone", &[]),
+        revoke.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::UnauthorizedMultipleOwnership { .. }
+    ));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attester", &[]),
+        revoke,
+    )
+    .unwrap();
+    assert!(query_profile(&deps, "maker").badges.is_empty());
+}
@@ -15,22 +15,35 @@ use localmoney_protocol::errors::ContractError::{
     OfferNotFound, RefundErrorNotExpired, TradeExpired,
 };
 use localmoney_protocol::guards::{
-    assert_migration_parameters, assert_ownership, assert_sender_is_buyer_or_seller,
-    assert_trade_state_and_type, assert_trade_state_change, assert_trade_state_change_is_valid,
-    assert_value_in_range, validate_min_max_items_per_page,
+    assert_contact_valid, assert_migration_parameters, assert_ownership,
+    assert_profile_not_suspended, assert_route_chains, assert_route_swaps,
+    assert_sender_is_buyer_or_seller, assert_taker_requirements, assert_trade_state_and_type,
+    assert_trade_state_change, assert_trade_state_change_is_valid, assert_value_in_range,
+    validate_min_max_items_per_page,
+};
+use localmoney_protocol::hub::{
+    HubConfig, HubState, ProtocolOperation, ReputationTier, VersionedConfig,
+};
+use localmoney_protocol::hub_utils::{
+    assert_not_paused, get_hub_admin, get_hub_config, get_pause_status, get_reputation_tiers,
+    get_trade_limits, get_versioned_hub_config, register_hub_internal, update_hub_config_internal,
+    update_hub_state_internal,
 };
-use localmoney_protocol::hub::HubConfig;
-use localmoney_protocol::hub_utils::{get_hub_admin, get_hub_config, register_hub_internal};
 use localmoney_protocol::offer::{load_offer, Arbitrator, OfferType, TradeInfo};
-use localmoney_protocol::price::{query_fiat_price_for_denom, DenomFiatPrice};
+use localmoney_protocol::price::{
+    denom_amount, fiat_value, price_error_from_query, query_denom_metadata,
+    query_fiat_price_for_denom, query_twap_price_for_denom, simulate_swap, DenomFiatPrice,
+};
 use localmoney_protocol::profile::{
-    load_profile, update_profile_contact_msg, update_profile_trades_count_msg,
+    add_profile_review_msg, is_blocked_between, load_encryption_key, load_profile,
+    update_profile_contact_msg, update_profile_disputes_count_msg,
+    update_profile_traded_volume_msg, update_profile_trades_count_msg, DisputeEvent, Review,
 };
 use localmoney_protocol::trade::{
     arbitrators, calc_denom_fiat_price, ArbitratorModel, ConversionRoute, ConversionStep,
     ExecuteMsg, FeeInfo, InstantiateMsg, MigrateMsg, NewTrade, QueryMsg, Swap, SwapMsg, Trade,
     TradeModel, TradeResponse, TradeState, TradeStateItem, TraderRole, DENOM_CONVERSION_ROUTE,
-    DENOM_CONVERSION_STEP,
+    DENOM_CONVERSION_STEP, PENDING_BURNS,
 };
 pub const SWAP_REPLY_ID: u64 = 1u64;
 
@@ -59,6 +72,8 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::RegisterHub {} => register_hub(deps, info),
+        ExecuteMsg::UpdateHubConfig(config) => update_hub_config(deps, info, config),
+        ExecuteMsg::UpdateHubState(state) => update_hub_state(deps, info, state),
         ExecuteMsg::Create(new_trade) => create_trade(deps, env, info, new_trade),
         ExecuteMsg::AcceptRequest {
             trade_id,
@@ -91,6 +106,12 @@ pub fn execute(
         ExecuteMsg::RegisterConversionRouteForDenom { denom, route } => {
             register_conversion_route_for_denom(deps, info, denom, route)
         }
+        ExecuteMsg::RateTrade {
+            trade_id,
+            rating,
+            comment,
+        } => rate_trade(deps, env, info, trade_id, rating, comment),
+        ExecuteMsg::RetryPendingBurn { denom } => retry_pending_burn(deps, denom),
     }
 }
 
@@ -120,8 +141,11 @@ fn create_trade(
     info: MessageInfo,
     new_trade: NewTrade,
 ) -> Result<Response, ContractError> {
-    // Load Hub Cfg
-    let hub_cfg = get_hub_config(deps.as_ref());
+    assert_not_paused(deps.as_ref(), ProtocolOperation::CreateTrade)?;
+
+    // Load Hub Cfg, its version is recorded on the trade for auditing
+    let versioned_hub_cfg = get_versioned_hub_config(deps.as_ref());
+    let hub_cfg = versioned_hub_cfg.config;
 
     // Load Offer
     let offer_id = new_trade.offer_id.clone();
@@ -137,7 +161,6 @@ fn create_trade(
     }
     let offer_result = offer_result.unwrap();
     let offer = offer_result.offer;
-    assert_value_in_range(offer.min_amount, offer.max_amount, new_trade.amount.clone())?;
 
     // Can't create Trade with the same wallet
     if info.sender.eq(&offer.owner) {
@@ -147,30 +170,114 @@ fn create_trade(
         });
     }
 
-    // Check if new_trade.amount in fiat is lower than the trade limit at hub_cfg
-    let offer_denom_usd_price = query_fiat_price_for_denom(
-        &deps.querier,
+    //Freeze the Denom price in Fiat using the rate set on Offer by the Maker
+    let denom_fiat_price = query_trade_price(
+        deps.as_ref(),
+        &hub_cfg,
+        offer.denom.clone(),
+        offer.fiat_currency.clone(),
+    )?;
+    let denom_final_price = calc_denom_fiat_price(offer.rate, denom_fiat_price.price);
+    if denom_final_price.is_zero() {
+        return Err(ContractError::InvalidPriceForDenom {});
+    }
+
+    // A fiat amount is converted at the offer price, and so are the offer fiat limits
+    let trade_amount = match new_trade.fiat_amount {
+        Some(_) if !new_trade.amount.is_zero() => {
+            return Err(ContractError::InvalidParameter {
+                parameter: "fiat_amount".to_string(),
+                message: Some("The amount can not be set along with the fiat_amount.".to_string()),
+            })
+        }
+        Some(fiat_amount) => {
+            denom_amount(fiat_amount, denom_final_price, denom_fiat_price.decimals)
+        }
+        None => new_trade.amount,
+    };
+    match &offer.fiat_limits {
+        Some(fiat_limits) => assert_value_in_range(
+            denom_amount(
+                fiat_limits.min,
+                denom_final_price,
+                denom_fiat_price.decimals,
+            ),
+            denom_amount(
+                fiat_limits.max,
+                denom_final_price,
+                denom_fiat_price.decimals,
+            ),
+            trade_amount,
+        )?,
+        None => assert_value_in_range(offer.min_amount, offer.max_amount, trade_amount)?,
+    }
+
+    // Check if the trade amount in fiat is lower than the trade limit at hub_cfg
+    let offer_denom_usd_price = query_trade_price(
+        deps.as_ref(),
+        &hub_cfg,
         offer.denom.clone(),
         FiatCurrency::USD,
-        hub_cfg.price_addr.to_string(),
-    )
-    .unwrap_or(DenomFiatPrice {
-        denom: offer.denom.clone(),
-        fiat: FiatCurrency::USD,
-        price: Uint256::from_u128(0),
-    });
+    )?;
     let offer_usd_price = calc_denom_fiat_price(offer.rate, offer_denom_usd_price.price);
-    let new_trade_amount = Uint256::from_u128(new_trade.amount.u128());
-    let usd_trade_amount = (new_trade_amount * offer_usd_price)
-        .checked_div(Uint256::from_u128(100u128))
-        .unwrap_or(Uint256::zero());
-    let usd_trade_amount = usd_trade_amount
-        .checked_div(Uint256::from_u128(1_000_000u128))
-        .unwrap_or(Uint256::zero());
-
-    // The min amount
-    let min_amount = Uint256::from_u128(hub_cfg.trade_limit_min);
-    let max_amount = Uint256::from_u128(hub_cfg.trade_limit_max);
+    let usd_trade_amount = fiat_value(
+        trade_amount,
+        offer_usd_price,
+        offer_denom_usd_price.decimals,
+    );
+
+    // The limits can be overridden at the Hub for the offer's fiat and denom
+    let trade_limits = get_trade_limits(
+        deps.as_ref(),
+        offer.fiat_currency.clone(),
+        offer.denom.clone(),
+    );
+    let min_amount = Uint256::from_u128(trade_limits.min);
+    let mut max_amount = Uint256::from_u128(trade_limits.max);
+
+    // The taker's reputation may lower the max amount
+    let taker_profile = load_profile(
+        &deps.querier,
+        hub_cfg.profile_addr.to_string(),
+        info.sender.clone(),
+    )
+    .unwrap();
+    assert_profile_not_suspended(&taker_profile, env.block.time.seconds())?;
+    if taker_profile.encryption_key.is_none() && new_trade.profile_taker_encryption_key.is_none() {
+        return Err(ContractError::MissingProfileEncryptionKey {
+            profile_addr: new_trade.taker.clone(),
+        });
+    }
+    // The contact shared on this trade doesn't change the taker profile
+    let taker_contact = new_trade.taker_contact.clone();
+    if taker_contact.is_empty() {
+        return Err(ContractError::MissingProfileContact {
+            profile_addr: new_trade.taker.clone(),
+        });
+    }
+    assert_contact_valid(&taker_contact)?;
+    if is_blocked_between(
+        &deps.querier,
+        hub_cfg.profile_addr.to_string(),
+        info.sender.clone(),
+        offer.owner.clone(),
+    )
+    .unwrap()
+    {
+        return Err(ContractError::CounterpartyBlocked {});
+    }
+    assert_taker_requirements(
+        &offer.taker_requirements,
+        &taker_profile,
+        env.block.time.seconds(),
+    )?;
+    if let Some(reputation_limit) = ReputationTier::trade_limit_for(
+        &get_reputation_tiers(deps.as_ref()),
+        &taker_profile,
+        env.block.time.seconds(),
+    ) {
+        max_amount = max_amount.min(Uint256::from_u128(reputation_limit));
+    }
 
     // Check that usd_trade_amount is lower or equal than the trade limit and return error if not.
     if usd_trade_amount < min_amount || usd_trade_amount > max_amount {
@@ -181,23 +288,6 @@ fn create_trade(
         });
     }
 
-    //Freeze the Denom price in Fiat using the rate set on Offer by the Maker
-    let denom_fiat_price = query_fiat_price_for_denom(
-        &deps.querier,
-        offer.denom.clone(),
-        offer.fiat_currency.clone(),
-        hub_cfg.price_addr.to_string(),
-    )
-    .unwrap_or(DenomFiatPrice {
-        denom: offer.denom.clone(),
-        fiat: offer.fiat_currency.clone(),
-        price: Uint256::from_u128(0),
-    });
-    let denom_final_price = calc_denom_fiat_price(offer.rate, denom_fiat_price.price);
-    if denom_final_price.is_zero() {
-        return Err(ContractError::InvalidPriceForDenom {});
-    }
-
     //Instantiate buyer and seller addresses according to Offer type (buy, sell)
     let buyer: Addr;
     let buyer_contact: Option<String>;
@@ -208,10 +298,10 @@ fn create_trade(
         buyer = offer.owner.clone(); // maker
         buyer_contact = None; // maker
         seller = new_trade.taker.clone(); // taker
-        seller_contact = Some(new_trade.taker_contact); // taker
+        seller_contact = Some(taker_contact); // taker
     } else {
         buyer = new_trade.taker.clone(); // taker
-        buyer_contact = Some(new_trade.taker_contact); // taker
+        buyer_contact = Some(taker_contact); // taker
         seller = offer.owner.clone(); // maker
         seller_contact = None // maker
     }
@@ -226,13 +316,20 @@ fn create_trade(
     };
     let trade_state_history = vec![new_trade_state];
 
+    let taker_encryption_key_version = match &new_trade.profile_taker_encryption_key {
+        Some(encryption_key) => taker_profile.encryption_key_version_for(encryption_key),
+        None => taker_profile.encryption_key_version,
+    };
     let mut sub_msgs = vec![];
-    sub_msgs.push(update_profile_contact_msg(
-        hub_cfg.profile_addr.to_string(),
-        new_trade.taker.clone(),
-        new_trade.profile_taker_contact,
-        new_trade.profile_taker_encryption_key,
-    ));
+    if new_trade.profile_taker_contact.is_some() || new_trade.profile_taker_encryption_key.is_some()
+    {
+        sub_msgs.push(update_profile_contact_msg(
+            hub_cfg.profile_addr.to_string(),
+            new_trade.taker.clone(),
+            new_trade.profile_taker_contact,
+            new_trade.profile_taker_encryption_key,
+        ));
+    }
 
     let random_seed: u32 = (env.block.time.seconds() % 100) as u32;
     let arbitrator = ArbitratorModel::get_arbitrator_random(
@@ -241,6 +338,28 @@ fn create_trade(
         offer.fiat_currency.clone(),
     );
 
+    // Snapshot the encryption keys versions, the taker key is updated along with the trade
+    let maker_encryption_key_version = load_profile(
+        &deps.querier,
+        hub_cfg.profile_addr.to_string(),
+        offer.owner.clone(),
+    )
+    .unwrap()
+    .encryption_key_version;
+    let arbitrator_encryption_key_version = load_profile(
+        &deps.querier,
+        hub_cfg.profile_addr.to_string(),
+        arbitrator.arbitrator.clone(),
+    )
+    .unwrap()
+    .encryption_key_version;
+    let (buyer_encryption_key_version, seller_encryption_key_version) =
+        if offer.offer_type == OfferType::Buy {
+            (maker_encryption_key_version, taker_encryption_key_version)
+        } else {
+            (taker_encryption_key_version, maker_encryption_key_version)
+        };
+
     let expires_at = env.block.time.seconds() + hub_cfg.trade_expiration_timer;
     //Instantiate Trade state
     let trade = TradeModel::create(
@@ -258,9 +377,14 @@ fn create_trade(
             env.block.time.seconds(),
             expires_at,
             offer.denom.clone(),
-            new_trade.amount.clone(),
+            trade_amount,
             offer.fiat_currency,
             denom_final_price,
+            usd_trade_amount,
+            versioned_hub_cfg.version,
+            buyer_encryption_key_version,
+            seller_encryption_key_version,
+            arbitrator_encryption_key_version,
             trade_state_history,
         ),
     )
@@ -286,12 +410,39 @@ fn create_trade(
         .add_attribute("denom_fiat_price", denom_fiat_price.price.to_string())
         .add_attribute("offer_rate", offer.rate.to_string())
         .add_attribute("taker", new_trade.taker.to_string())
+        .add_attribute("config_version", trade.config_version.to_string())
         .add_attribute("usd_trade_amount", usd_trade_amount.to_string())
         .add_attribute("offer_usd_price", offer_usd_price.to_string());
 
     Ok(res)
 }
 
+// Trades are priced on a TWAP when the hub sets a window, and on spot otherwise
+fn query_trade_price(
+    deps: Deps,
+    hub_cfg: &HubConfig,
+    denom: Denom,
+    fiat: FiatCurrency,
+) -> Result<DenomFiatPrice, ContractError> {
+    let price = if hub_cfg.trade_price_twap_window > 0 {
+        query_twap_price_for_denom(
+            &deps.querier,
+            denom.clone(),
+            fiat.clone(),
+            hub_cfg.trade_price_twap_window,
+            hub_cfg.price_addr.to_string(),
+        )
+    } else {
+        query_fiat_price_for_denom(
+            &deps.querier,
+            denom.clone(),
+            fiat.clone(),
+            hub_cfg.price_addr.to_string(),
+        )
+    };
+    price.map_err(|e| price_error_from_query(&denom, &fiat, e))
+}
+
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -311,6 +462,11 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             deps.storage,
             fiat,
         )?),
+        QueryMsg::PendingBurn { denom } => to_binary(
+            &PENDING_BURNS
+                .may_load(deps.storage, denom_to_string(&denom).as_str())?
+                .unwrap_or_default(),
+        ),
     }
 }
 
@@ -321,33 +477,28 @@ fn register_hub<T: CustomQuery>(
     register_hub_internal(info.sender, deps.storage, HubAlreadyRegistered {})
 }
 
+fn update_hub_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Box<VersionedConfig>,
+) -> Result<Response, ContractError> {
+    update_hub_config_internal(info.sender, deps.storage, *config)
+}
+
+fn update_hub_state(
+    deps: DepsMut,
+    info: MessageInfo,
+    state: Box<HubState>,
+) -> Result<Response, ContractError> {
+    update_hub_state_internal(info.sender, deps.storage, *state)
+}
+
 fn query_trade<T: CustomQuery>(env: Env, deps: Deps<T>, id: u64) -> StdResult<TradeInfo> {
     let hub_config = get_hub_config(deps);
     let state = TradeModel::from_store(deps.storage, id);
 
-    let buyer = load_profile(
-        &deps.querier,
-        hub_config.profile_addr.to_string(),
-        state.buyer.clone(),
-    )
-    .unwrap();
-
-    let seller = load_profile(
-        &deps.querier,
-        hub_config.profile_addr.to_string(),
-        state.seller.clone(),
-    )
-    .unwrap();
-
-    let arbitrator = load_profile(
-        &deps.querier,
-        hub_config.profile_addr.to_string(),
-        state.arbitrator.clone(),
-    )
-    .unwrap();
-
     let block_time = env.block.time.seconds();
-    let trade = TradeResponse::map(state, buyer, seller, arbitrator, block_time);
+    let trade = map_trade_response(deps, &hub_config, state, block_time);
     let offer = load_offer(
         &deps.querier,
         trade.offer_id.clone(),
@@ -358,6 +509,37 @@ fn query_trade<T: CustomQuery>(env: Env, deps: Deps<T>, id: u64) -> StdResult<Tr
     Ok(TradeInfo { trade, offer })
 }
 
+// Loads the encryption keys each party had when the trade was created
+fn map_trade_response<T: CustomQuery>(
+    deps: Deps<T>,
+    hub_config: &HubConfig,
+    trade: Trade,
+    block_time: u64,
+) -> TradeResponse {
+    let load_key = |addr: Addr, version: u64| {
+        load_encryption_key(
+            &deps.querier,
+            hub_config.profile_addr.to_string(),
+            addr,
+            Some(version),
+        )
+        .unwrap()
+    };
+    let buyer_encryption_key = load_key(trade.buyer.clone(), trade.buyer_encryption_key_version);
+    let seller_encryption_key = load_key(trade.seller.clone(), trade.seller_encryption_key_version);
+    let arbitrator_encryption_key = load_key(
+        trade.arbitrator.clone(),
+        trade.arbitrator_encryption_key_version,
+    );
+    TradeResponse::map(
+        trade,
+        buyer_encryption_key,
+        seller_encryption_key,
+        arbitrator_encryption_key,
+        block_time,
+    )
+}
+
 pub fn query_trades<T: CustomQuery>(
     env: Env,
     deps: Deps<T>,
@@ -385,32 +567,10 @@ pub fn query_trades<T: CustomQuery>(
         let offer_contract = trade.offer_contract.to_string();
         let offer_response = load_offer(&deps.querier, offer_id, offer_contract).unwrap();
 
-        // TODO change it to make only one query
-        let buyer = load_profile(
-            &deps.querier,
-            hub_config.profile_addr.to_string(),
-            trade.buyer.clone(),
-        )
-        .unwrap();
-
-        let seller = load_profile(
-            &deps.querier,
-            hub_config.profile_addr.to_string(),
-            trade.seller.clone(),
-        )
-        .unwrap();
-
-        let arbitrator = load_profile(
-            &deps.querier,
-            hub_config.profile_addr.to_string(),
-            trade.arbitrator.clone(),
-        )
-        .unwrap();
-
         let block_time = env.block.time.seconds();
 
         trades_infos.push(TradeInfo {
-            trade: TradeResponse::map(trade.clone(), buyer, seller, arbitrator, block_time),
+            trade: map_trade_response(deps, &hub_config, trade.clone(), block_time),
             offer: offer_response,
         })
     });
@@ -425,6 +585,8 @@ fn fund_escrow(
     trade_id: u64,
     maker_contact: Option<String>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::FundEscrow)?;
+
     // Load HubConfig, Trade & Offer
     let hub_config = get_hub_config(deps.as_ref());
     let mut trade = TradeModel::from_store(deps.storage, trade_id);
@@ -530,10 +692,24 @@ fn accept_request(
     trade_id: u64,
     maker_contact: String,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::AcceptRequest)?;
+
     let mut trade = TradeModel::from_store(deps.storage, trade_id);
     // Only the buyer can accept the request
     assert_ownership(info.sender.clone(), trade.buyer.clone()).unwrap();
 
+    // Load Hub Cfg
+    let hub_config = get_hub_config(deps.as_ref());
+
+    // Suspended makers can't accept new requests
+    let maker_profile = load_profile(
+        &deps.querier,
+        hub_config.profile_addr.to_string(),
+        info.sender.clone(),
+    )
+    .unwrap();
+    assert_profile_not_suspended(&maker_profile, env.block.time.seconds())?;
+
     // Only change state if the current state is TradeState::RequestCreated
     assert_trade_state_change_is_valid(
         trade.get_state(),
@@ -550,9 +726,6 @@ fn accept_request(
 
     TradeModel::store(deps.storage, &trade).unwrap();
 
-    // Load Hub Cfg
-    let hub_config = get_hub_config(deps.as_ref());
-
     let sub_msgs = create_update_trades_count_msgs(
         hub_config.profile_addr.to_string(),
         trade.buyer.clone(),
@@ -633,11 +806,10 @@ fn cancel_request(
     )
     .unwrap();
 
+    let hub_config = get_hub_config(deps.as_ref());
     let mut sub_msgs: Vec<SubMsg> = vec![];
     // Should not be called when the current state is TradeState::RequestCreated
     if vec![TradeState::EscrowFunded, TradeState::RequestAccepted].contains(&trade.get_state()) {
-        // Load hub config
-        let hub_config = get_hub_config(deps.as_ref());
         let mut profile_submsgs = create_update_trades_count_msgs(
             hub_config.profile_addr.to_string(),
             trade.buyer.clone(),
@@ -646,6 +818,22 @@ fn cancel_request(
         );
         sub_msgs.append(&mut profile_submsgs)
     }
+    // Only counted on the profiles, requests left to expire before being funded are
+    // counted as expired when they're canceled
+    let counted_state = if trade.get_state().ne(&TradeState::EscrowFunded)
+        && trade.request_expired(env.block.time.seconds())
+    {
+        TradeState::RequestExpired
+    } else {
+        TradeState::RequestCanceled
+    };
+    let mut profile_submsgs = create_update_trades_count_msgs(
+        hub_config.profile_addr.to_string(),
+        trade.buyer.clone(),
+        trade.seller.clone(),
+        counted_state,
+    );
+    sub_msgs.append(&mut profile_submsgs);
 
     if trade.get_state().eq(&TradeState::EscrowFunded) {
         // Update trade State to TradeState::EscrowCanceled
@@ -723,6 +911,15 @@ fn release_escrow(
         TradeState::EscrowReleased,
     );
     send_msgs.append(&mut profile_submsgs);
+    for trader in [trade.buyer.clone(), trade.seller.clone()] {
+        send_msgs.push(update_profile_traded_volume_msg(
+            hub_config.profile_addr.to_string(),
+            trader,
+            trade.denom.clone(),
+            trade.amount,
+            trade.usd_amount,
+        ));
+    }
 
     // Send tokens to buyer
     send_msgs.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
@@ -769,6 +966,7 @@ fn refund_escrow(
     }
 
     let mut trade: Trade = TradeModel::from_store(deps.storage, trade_id);
+    let previous_state = trade.get_state();
 
     //Update trade state to TradeState::EscrowRefunded
     trade.set_state(TradeState::EscrowRefunded, &env, &info);
@@ -782,6 +980,16 @@ fn refund_escrow(
         trade.seller.clone(),
         TradeState::EscrowRefunded,
     );
+    // Funded escrows are only refunded once expired, canceled ones were counted when canceled
+    if previous_state.eq(&TradeState::EscrowFunded) {
+        let mut profile_submsgs = create_update_trades_count_msgs(
+            hub_config.profile_addr.to_string(),
+            trade.buyer.clone(),
+            trade.seller.clone(),
+            TradeState::RequestExpired,
+        );
+        sub_msgs.append(&mut profile_submsgs);
+    }
 
     let amount = trade.amount.clone();
     let denom = denom_to_string(&trade.denom);
@@ -816,8 +1024,8 @@ pub fn create_arbitrator(
     let create_profile_sub_msg = update_profile_contact_msg(
         hub_config.profile_addr.to_string(),
         arbitrator_address.clone(),
-        "N/A".to_string(),
-        encryption_key.clone(),
+        None,
+        Some(encryption_key.clone()),
     );
 
     let res = Response::new()
@@ -892,7 +1100,15 @@ fn dispute_escrow(
     trade.arbitrator_seller_contact = Some(seller_contact);
     TradeModel::store(deps.storage, &trade).unwrap();
 
+    let hub_config = get_hub_config(deps.as_ref());
+    let dispute_opened_msg = update_profile_disputes_count_msg(
+        hub_config.profile_addr.to_string(),
+        info.sender.clone(),
+        DisputeEvent::Opened,
+    );
+
     let res = Response::new()
+        .add_submessage(dispute_opened_msg)
         .add_attribute("action", "dispute_escrow")
         .add_attribute("trade_id", trade.id.to_string())
         .add_attribute("state", trade.get_state().to_string())
@@ -945,17 +1161,19 @@ fn settle_dispute(
     };
 
     // Check if winner is eligible, it must be either maker or taker
-    if winner.eq(&maker) {
+    let loser = if winner.eq(&maker) {
         trade.set_state(TradeState::SettledForMaker, &env, &info);
+        taker.clone()
     } else if winner.eq(&taker) {
-        trade.set_state(TradeState::SettledForTaker, &env, &info)
+        trade.set_state(TradeState::SettledForTaker, &env, &info);
+        maker.clone()
     } else {
         return Err(ContractError::InvalidSender {
             sender: winner,
             buyer: trade.buyer,
             seller: trade.seller,
         });
-    }
+    };
     TradeModel::store(deps.storage, &trade).unwrap();
 
     // Collect Protocol Fees
@@ -995,6 +1213,18 @@ fn settle_dispute(
         trade.seller.clone(),
         trade.get_state(),
     );
+    let disputes_submsgs = vec![
+        update_profile_disputes_count_msg(
+            hub_config.profile_addr.to_string(),
+            winner.clone(),
+            DisputeEvent::Won,
+        ),
+        update_profile_disputes_count_msg(
+            hub_config.profile_addr.to_string(),
+            loser,
+            DisputeEvent::Lost,
+        ),
+    ];
 
     let res = Response::new()
         .add_attribute("arbitrator", trade.arbitrator.to_string())
@@ -1002,10 +1232,60 @@ fn settle_dispute(
         .add_attribute("maker", maker.to_string())
         .add_attribute("taker", taker.to_string())
         .add_submessages(profile_submsgs)
+        .add_submessages(disputes_submsgs)
         .add_submessages(send_msgs);
     Ok(res)
 }
 
+/// Rates the counterparty of a finished trade, the review is stored on its profile.
+fn rate_trade(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    trade_id: u64,
+    rating: u8,
+    comment: Option<String>,
+) -> Result<Response, ContractError> {
+    let trade = TradeModel::from_store(deps.storage, trade_id);
+    assert_sender_is_buyer_or_seller(
+        info.sender.clone(),
+        trade.buyer.clone(),
+        trade.seller.clone(),
+    )?;
+    match trade.get_state() {
+        TradeState::EscrowReleased | TradeState::SettledForMaker | TradeState::SettledForTaker => {}
+        current => return Err(ContractError::TradeNotRateable { current }),
+    }
+
+    let counterparty = if info.sender.eq(&trade.buyer) {
+        trade.seller.clone()
+    } else {
+        trade.buyer.clone()
+    };
+    let review = Review {
+        trade_id,
+        reviewer: info.sender.clone(),
+        rating,
+        comment,
+        created_at: env.block.time.seconds(),
+    };
+    let hub_config = get_hub_config(deps.as_ref());
+    let review_msg = add_profile_review_msg(
+        hub_config.profile_addr.to_string(),
+        counterparty.clone(),
+        review,
+    );
+
+    let res = Response::new()
+        .add_submessage(review_msg)
+        .add_attribute("action", "rate_trade")
+        .add_attribute("trade_id", trade_id.to_string())
+        .add_attribute("reviewer", info.sender.to_string())
+        .add_attribute("reviewee", counterparty.to_string())
+        .add_attribute("rating", rating.to_string());
+    Ok(res)
+}
+
 /// Registers a conversion route for a given denom.
 fn register_conversion_route_for_denom(
     deps: DepsMut,
@@ -1017,6 +1297,31 @@ fn register_conversion_route_for_denom(
     let admin = get_hub_admin(deps.as_ref()).addr;
     assert_ownership(info.sender, admin)?;
 
+    // The route must chain from the denom to $LOCAL and every pool must answer a simulation
+    let hub_config = get_hub_config(deps.as_ref());
+    let hops: Vec<(&Denom, &Denom)> = route
+        .iter()
+        .map(|step| (&step.offer_asset, &step.ask_asset))
+        .collect();
+    assert_route_chains(&denom, &hops, &hub_config.local_denom)?;
+    // The dry-run swaps a whole unit of the denom, as priced by the price contract
+    let metadata = query_denom_metadata(
+        &deps.querier,
+        denom.clone(),
+        hub_config.price_addr.to_string(),
+    )
+    .map_err(|e| ContractError::InvalidRoute {
+        denom: denom_to_string(&denom),
+        message: format!("The denom metadata can't be queried: {}", e),
+    })?
+    .ok_or_else(|| ContractError::InvalidRoute {
+        denom: denom_to_string(&denom),
+        message: "The denom has no metadata in the price contract.".to_string(),
+    })?;
+    assert_route_swaps(&denom, metadata.decimals, &route, |step, amount| {
+        simulate_swap(&deps.querier, &step.pool, &step.offer_asset, amount)
+    })?;
+
     // Store conversion route
     let denom = denom_to_string(&denom);
     DENOM_CONVERSION_ROUTE
@@ -1029,6 +1334,32 @@ fn register_conversion_route_for_denom(
     Ok(res)
 }
 
+// Burns what was kept while swaps were paused, anyone can trigger it once swaps are resumed
+fn retry_pending_burn(deps: DepsMut, denom: Denom) -> Result<Response, ContractError> {
+    assert_not_paused(deps.as_ref(), ProtocolOperation::Swap)?;
+    let hub_config = get_hub_config(deps.as_ref());
+    let trade_denom = denom_to_string(&denom);
+    let pending_burn = PENDING_BURNS
+        .may_load(deps.storage, trade_denom.as_str())
+        .unwrap()
+        .unwrap_or_default();
+    if pending_burn.is_zero() {
+        return Err(ContractError::InvalidParameter {
+            parameter: "denom".to_string(),
+            message: Some(format!("There is no pending burn for {}.", trade_denom)),
+        });
+    }
+    PENDING_BURNS.remove(deps.storage, trade_denom.as_str());
+    let swap_msg = swap_and_burn_msg(deps, &hub_config, &trade_denom, pending_burn);
+
+    let res = Response::new()
+        .add_submessage(swap_msg)
+        .add_attribute("action", "retry_pending_burn")
+        .add_attribute("denom", trade_denom)
+        .add_attribute("burn_amount", pending_burn.to_string());
+    Ok(res)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
@@ -1184,6 +1515,58 @@ fn calculate_fees(hub_config: &HubConfig, amount: Uint128) -> FeeInfo {
     }
 }
 
+// Swaps the burn amount following the ConversionRoute of the trade denom, it's burned on swap reply.
+fn swap_and_burn_msg(
+    deps: DepsMut,
+    hub_cfg: &HubConfig,
+    trade_denom: &str,
+    burn_amount: Uint128,
+) -> SubMsg {
+    // Load the ConversionRoute route for trade_denom
+    let conversion_route = DENOM_CONVERSION_ROUTE
+        .load(deps.storage, trade_denom)
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+
+    // Query the contract's balance of the the ConversionRoute's ask_asset
+    let ask_asset_balance = deps
+        .querier
+        .query_balance(
+            hub_cfg.trade_addr.to_string(),
+            denom_to_string(&conversion_route.ask_asset),
+        )
+        .unwrap_or(Coin::new(
+            0u128,
+            denom_to_string(&conversion_route.ask_asset),
+        ));
+
+    // Store the ConversionStep
+    DENOM_CONVERSION_STEP
+        .save(
+            deps.storage,
+            &ConversionStep {
+                trade_denom: Denom::Native(trade_denom.to_string()),
+                step_previous_balance: ask_asset_balance,
+                step: 0,
+            },
+        )
+        .unwrap();
+
+    // Add message to swap the burn_amount and burn it on swap reply
+    SubMsg {
+        id: SWAP_REPLY_ID,
+        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: conversion_route.pool.to_string(),
+            msg: to_binary(&SwapMsg { swap: Swap {} }).unwrap(),
+            funds: vec![coin(burn_amount.u128(), trade_denom)],
+        }),
+        gas_limit: None,
+        reply_on: ReplyOn::Success,
+    }
+}
+
 // Adds protocol fees to the given send_msgs.
 fn add_protocol_fees_msgs(
     deps: DepsMut,
@@ -1198,51 +1581,23 @@ fn add_protocol_fees_msgs(
     // Protocol Fee (Burn)
     if !fee_info.burn_amount.is_zero() {
         //If coin being traded is not $LOCAL, swap it and burn it on swap reply.
+        //While swaps are paused, the burn amount is kept on the contract so the release can't fail,
+        //and recorded so the burn can be retried once swaps are resumed.
         let local_denom = denom_to_string(&hub_cfg.local_denom);
-        if trade_denom.ne(&local_denom) {
-            // Load the ConversionRoute route for trade_denom
-            let conversion_route = DENOM_CONVERSION_ROUTE
-                .load(deps.storage, &trade_denom)
-                .unwrap()
-                .first()
-                .unwrap()
-                .clone();
-
-            // Query the contract's balance of the the ConversionRoute's ask_asset
-            let ask_asset_balance = deps
-                .querier
-                .query_balance(
-                    hub_cfg.trade_addr.to_string(),
-                    denom_to_string(&conversion_route.ask_asset),
-                )
-                .unwrap_or(Coin::new(
-                    0u128,
-                    denom_to_string(&conversion_route.ask_asset),
-                ));
-
-            // Store the ConversionStep
-            DENOM_CONVERSION_STEP
-                .save(
-                    deps.storage,
-                    &ConversionStep {
-                        trade_denom: Denom::Native(trade_denom.clone()),
-                        step_previous_balance: ask_asset_balance,
-                        step: 0,
-                    },
-                )
+        let swap_paused = get_pause_status(deps.as_ref()).is_paused(&ProtocolOperation::Swap);
+        if trade_denom.ne(&local_denom) && swap_paused {
+            PENDING_BURNS
+                .update(deps.storage, trade_denom.as_str(), |pending_burn| {
+                    StdResult::Ok(pending_burn.unwrap_or_default() + fee_info.burn_amount)
+                })
                 .unwrap();
-
-            // Add message to swap the burn_amount and burn it on swap reply
-            send_msgs.push(SubMsg {
-                id: SWAP_REPLY_ID,
-                msg: CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: conversion_route.pool.to_string(),
-                    msg: to_binary(&SwapMsg { swap: Swap {} }).unwrap(),
-                    funds: vec![coin(fee_info.burn_amount.u128(), trade_denom.clone())],
-                }),
-                gas_limit: None,
-                reply_on: ReplyOn::Success,
-            });
+        } else if trade_denom.ne(&local_denom) {
+            send_msgs.push(swap_and_burn_msg(
+                deps,
+                hub_cfg,
+                &trade_denom,
+                fee_info.burn_amount,
+            ));
         } else {
             //If coin being traded is $LOCAL, add message burning the local_burn amount
             send_msgs.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Burn {
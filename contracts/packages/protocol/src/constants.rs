@@ -1,7 +1,22 @@
 pub const BASE_ORACLE_DENOM: &str = "ATOM";
+pub const BASE_ORACLE_DECIMALS: u8 = 6;
+pub const DEFAULT_DENOM_DECIMALS: u8 = 6;
+pub const MAX_DENOM_DECIMALS: u8 = 18;
 pub const OFFER_DESCRIPTION_LIMIT: usize = 140;
+pub const OFFER_ADDRESS_FILTER_LIMIT: usize = 50;
+pub const REVIEW_COMMENT_LIMIT: usize = 140;
+pub const SUSPENSION_REASON_LIMIT: usize = 140;
+pub const BLOCKED_USERS_LIMIT: usize = 100;
+pub const CONTACT_LIMIT: usize = 1024;
+pub const ENCRYPTION_KEY_LIMIT: usize = 1024;
+pub const BADGE_NAME_LIMIT: usize = 32;
+pub const BADGES_LIMIT: usize = 10;
+pub const MIN_RATING: u8 = 1;
+pub const MAX_RATING: u8 = 5;
 pub const MAX_PLATFORM_FEE: u64 = 10; // 10%
 pub const MAX_TRADE_EXPIRATION_TIMER: u64 = 172800; // 2 days
 pub const MAX_TRADE_DISPUTE_TIMER: u64 = 86400; // 1 day
+pub const MAX_CONFIG_TIMELOCK: u64 = 1209600; // 14 days
 pub const MIN_ITEMS_PER_PAGE: u32 = 1; // Pagination
 pub const MAX_ITEMS_PER_PAGE: u32 = 30; // Pagination
+pub const PRICE_HISTORY_LIMIT: u32 = 48; // Samples kept per fiat and per denom
@@ -1,6 +1,8 @@
+use crate::currencies::FiatCurrency;
+use crate::hub::ProtocolOperation;
 use crate::offer::OfferState;
 use crate::trade::TradeState;
-use cosmwasm_std::{Addr, Uint128, Uint256, Uint64};
+use cosmwasm_std::{Addr, Decimal, Uint128, Uint256, Uint64};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -20,6 +22,16 @@ pub enum ContractError {
     HubAlreadyRegistered {},
     #[error("The sum of `chain_fee_pct`, `burn_fee_pct` and `warchest_fee_pct` must be less than {0}%.", max_platform_fee.to_string())]
     InvalidPlatformFee { max_platform_fee: Uint64 },
+    #[error("There is no pending config update.")]
+    NoPendingConfig {},
+    #[error("The config update is timelocked until {executable_at:?}.")]
+    ConfigTimelocked { executable_at: u64 },
+    #[error("A config update executable at {executable_at:?} is pending, cancel it first.")]
+    ConfigUpdatePending { executable_at: u64 },
+    #[error("There is no pending admin transfer.")]
+    NoPendingAdmin {},
+    #[error("The operation {operation} is paused.")]
+    OperationPaused { operation: ProtocolOperation },
     /// Offer Errors
     #[error("Min amount must be greater than Max amount.")]
     InvalidMinMax { min: Uint128, max: Uint128 },
@@ -36,6 +48,8 @@ pub enum ContractError {
         max_amount: Uint256,
         trading_limit: Uint256,
     },
+    #[error("Invalid taker requirements. {message}")]
+    InvalidTakerRequirements { message: String },
     #[error("Offer not found.")]
     OfferNotFound { offer_id: String },
     #[error("Value out of range.")]
@@ -58,6 +72,52 @@ pub enum ContractError {
     InvalidDenom { expected: String, received: String },
     #[error("Invalid price for denom. Must be greater than zero.")]
     InvalidPriceForDenom {},
+    #[error(
+        "The {fiat} price is stale. Updated at: {updated_at:?}. Max age: {max_age:?} seconds."
+    )]
+    StaleFiatPrice {
+        fiat: FiatCurrency,
+        updated_at: u64,
+        max_age: u64,
+    },
+    #[error(
+        "The {denom} price is stale. Updated at: {updated_at:?}. Max age: {max_age:?} seconds."
+    )]
+    StaleDenomPrice {
+        denom: String,
+        updated_at: u64,
+        max_age: u64,
+    },
+    #[error(
+        "The pushed {symbol} price is stale. Updated at: {updated_at:?}. Max age: {max_age:?} seconds."
+    )]
+    StalePushedPrice {
+        symbol: String,
+        updated_at: u64,
+        max_age: u64,
+    },
+    #[error("The {fiat} price is halted until its pending price is confirmed.")]
+    FiatPriceHalted { fiat: FiatCurrency },
+    #[error("There is no pending {fiat} price.")]
+    NoPendingPrice { fiat: FiatCurrency },
+    #[error("{caller} is not a registered price provider.")]
+    UnauthorizedPriceProvider { caller: Addr },
+    #[error("There is no price route for {denom}.")]
+    PriceRouteNotFound { denom: String },
+    #[error("There is no {fiat} price.")]
+    FiatPriceNotFound { fiat: FiatCurrency },
+    #[error("The oracle price of {symbol} is unavailable. {message}")]
+    OracleUnavailable { symbol: String, message: String },
+    #[error("The simulation on pool {pool} failed. {message}")]
+    PoolSimulationFailed { pool: String, message: String },
+    #[error("The {denom} price in {fiat} is unavailable. {message}")]
+    PriceUnavailable {
+        denom: String,
+        fiat: FiatCurrency,
+        message: String,
+    },
+    #[error("Invalid route for {denom}. {message}")]
+    InvalidRoute { denom: String, message: String },
     #[error("Invalid sender, must be Trade's buyer or seller.")]
     InvalidSender {
         sender: Addr,
@@ -85,11 +145,52 @@ pub enum ContractError {
     SwapErrorInvalidAmount {},
     #[error("Swap Error: missing denom.")]
     SwapErrorMissingDenom { expected_denom: String },
+    #[error("Only finished trades can be rated. Current state: {current}.")]
+    TradeNotRateable { current: TradeState },
     #[error("Unknown reply id: {reply_id:?}")]
     UnknownReplyId { reply_id: u64 },
+    #[error("The taker must have at least {min_released_trades:?} released trades, but has {released_trades_count:?}.")]
+    TakerReleasedTradesTooLow {
+        min_released_trades: u64,
+        released_trades_count: u64,
+    },
+    #[error("The taker profile must be at least {min_profile_age:?} seconds old, but is {profile_age:?} seconds old.")]
+    TakerProfileTooNew {
+        min_profile_age: u64,
+        profile_age: u64,
+    },
+    #[error("The taker dispute loss ratio must be at most {max_dispute_loss_ratio}, but is {dispute_loss_ratio}.")]
+    TakerDisputeLossRatioTooHigh {
+        max_dispute_loss_ratio: Decimal,
+        dispute_loss_ratio: Decimal,
+    },
+    #[error("The taker {taker} is not allowed to trade on this offer.")]
+    TakerAddressNotAllowed { taker: Addr },
     /// Profile Errors
+    #[error("The taker must have the {badge} badge.")]
+    TakerBadgeMissing { badge: String },
+    #[error("{attester} is not allowed to attest the {badge} badge.")]
+    UnauthorizedAttester { attester: Addr, badge: String },
+    #[error("The profile {profile_addr} has no {badge} badge.")]
+    BadgeNotFound { profile_addr: Addr, badge: String },
     #[error("Active offers limit reached. Limit: {limit:?}.")]
     ActiveOffersLimitReached { limit: u8 },
     #[error("Active trades limit reached. Limit: {limit:?}.")]
     ActiveTradesLimitReached { limit: u8 },
+    #[error("The profile {profile_addr} is suspended. Reason: {reason}")]
+    ProfileSuspended {
+        profile_addr: Addr,
+        reason: String,
+        expires_at: Option<u64>,
+    },
+    #[error("The profile {profile_addr} has no contact.")]
+    MissingProfileContact { profile_addr: Addr },
+    #[error("The profile {profile_addr} has no encryption key.")]
+    MissingProfileEncryptionKey { profile_addr: Addr },
+    #[error("Blocked users limit reached. Limit: {limit:?}.")]
+    BlockedUsersLimitReached { limit: usize },
+    #[error("A trade can not be created between users who blocked each other.")]
+    CounterpartyBlocked {},
+    #[error("Trade {trade_id:?} was already reviewed.")]
+    TradeAlreadyReviewed { trade_id: u64 },
 }
@@ -1,9 +1,17 @@
-use crate::constants::{MAX_ITEMS_PER_PAGE, MIN_ITEMS_PER_PAGE, OFFER_DESCRIPTION_LIMIT};
+use crate::constants::{
+    BADGES_LIMIT, BADGE_NAME_LIMIT, CONTACT_LIMIT, ENCRYPTION_KEY_LIMIT, MAX_DENOM_DECIMALS,
+    MAX_ITEMS_PER_PAGE, MAX_RATING, MIN_ITEMS_PER_PAGE, MIN_RATING, OFFER_ADDRESS_FILTER_LIMIT,
+    OFFER_DESCRIPTION_LIMIT, REVIEW_COMMENT_LIMIT, SUSPENSION_REASON_LIMIT,
+};
+use crate::denom_utils::denom_to_string;
 use crate::errors::ContractError;
-use crate::offer::OfferType;
+use crate::offer::{OfferType, TakerRequirements};
+use crate::profile::{Profile, Review};
 use crate::trade::{Trade, TradeState};
-use cosmwasm_std::{Addr, Uint128, Uint256};
+use cosmwasm_std::{Addr, Decimal, StdResult, Uint128, Uint256};
 use cw2::ContractVersion;
+use cw20::Denom;
+use std::fmt;
 
 pub fn assert_multiple_ownership(caller: Addr, owners: Vec<Addr>) -> Result<(), ContractError> {
     if owners.contains(&caller) {
@@ -149,6 +157,120 @@ pub fn assert_offer_description_valid(description: Option<String>) -> Result<(),
     };
 }
 
+// Contacts are encrypted by the client and keys are exported as base64
+pub fn assert_contact_valid(contact: &str) -> Result<(), ContractError> {
+    assert_base64_param("contact", contact, CONTACT_LIMIT)
+}
+
+pub fn assert_encryption_key_valid(encryption_key: &str) -> Result<(), ContractError> {
+    assert_base64_param("encryption_key", encryption_key, ENCRYPTION_KEY_LIMIT)
+}
+
+fn assert_base64_param(parameter: &str, value: &str, limit: usize) -> Result<(), ContractError> {
+    let message = if value.is_empty() {
+        Some("It can not be empty.".to_string())
+    } else if value.len() > limit {
+        Some(format!("It can not be longer than {} characters.", limit))
+    } else {
+        let padding = value.len() - value.trim_end_matches('=').len();
+        // Base64 is written in complete blocks of 4 characters
+        let is_base64 = value.as_bytes().chunks(4).all(|block| block.len() == 4)
+            && padding <= 2
+            && value
+                .trim_end_matches('=')
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/');
+        if is_base64 {
+            None
+        } else {
+            Some("It must be base64 encoded.".to_string())
+        }
+    };
+    match message {
+        Some(message) => Err(ContractError::InvalidParameter {
+            parameter: parameter.to_string(),
+            message: Some(message),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// A route of `(offer_asset, ask_asset)` hops must start at `denom`, each hop must swap
+/// what the previous one returned and the last one must return `target`.
+pub fn assert_route_chains(
+    denom: &Denom,
+    hops: &[(&Denom, &Denom)],
+    target: &Denom,
+) -> Result<(), ContractError> {
+    let invalid_route = |message: String| ContractError::InvalidRoute {
+        denom: denom_to_string(denom),
+        message,
+    };
+    let mut expected_offer = denom;
+    for (step, (offer_asset, ask_asset)) in hops.iter().enumerate() {
+        if offer_asset.ne(&expected_offer) {
+            return Err(invalid_route(format!(
+                "Step {} offers {}, but {} was expected.",
+                step,
+                denom_to_string(offer_asset),
+                denom_to_string(expected_offer)
+            )));
+        }
+        expected_offer = ask_asset;
+    }
+    match hops.last() {
+        None => Err(invalid_route("The route is empty.".to_string())),
+        Some((_, ask_asset)) if ask_asset.ne(&target) => Err(invalid_route(format!(
+            "The route ends in {}, but must end in {}.",
+            denom_to_string(ask_asset),
+            denom_to_string(target)
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Dry-runs the hops of a route on a whole unit of `denom`, each hop swapping what the previous
+/// one returned. `quote` simulates a hop, none of them may fail or return nothing.
+pub fn assert_route_swaps<H: fmt::Display>(
+    denom: &Denom,
+    decimals: u8,
+    hops: &[H],
+    quote: impl Fn(&H, Uint128) -> StdResult<Uint128>,
+) -> Result<(), ContractError> {
+    assert_decimals_valid(decimals)?;
+    let invalid_route = |message: String| ContractError::InvalidRoute {
+        denom: denom_to_string(denom),
+        message,
+    };
+    let amount = Uint128::new(10u128.pow(decimals as u32));
+    hops.iter()
+        .try_fold(amount, |amount, hop| match quote(hop, amount) {
+            Ok(returned) if returned.is_zero() => {
+                Err(invalid_route(format!("The hop {} returns nothing.", hop)))
+            }
+            Ok(returned) => Ok(returned),
+            Err(e) => Err(invalid_route(format!(
+                "The hop {} failed to simulate the swap: {}",
+                hop, e
+            ))),
+        })
+        .map(|_| ())
+}
+
+// Prices scale amounts by 10^decimals, which must fit in the 128 bits amounts
+pub fn assert_decimals_valid(decimals: u8) -> Result<(), ContractError> {
+    if decimals > MAX_DENOM_DECIMALS {
+        return Err(ContractError::InvalidParameter {
+            parameter: "decimals".to_string(),
+            message: Some(format!(
+                "This value can not be greater than {}.",
+                MAX_DENOM_DECIMALS
+            )),
+        });
+    }
+    Ok(())
+}
+
 pub fn assert_migration_parameters(
     previous_contract_version: ContractVersion,
     contract_name: String,
@@ -180,3 +302,218 @@ pub fn assert_migration_parameters(
 pub fn validate_min_max_items_per_page(limit: u32) -> u32 {
     limit.max(MIN_ITEMS_PER_PAGE).min(MAX_ITEMS_PER_PAGE)
 }
+
+pub fn assert_taker_requirements_valid(
+    taker_requirements: &Option<TakerRequirements>,
+) -> Result<(), ContractError> {
+    let taker_requirements = match taker_requirements {
+        Some(taker_requirements) => taker_requirements,
+        None => return Ok(()),
+    };
+    if let Some(max_dispute_loss_ratio) = taker_requirements.max_dispute_loss_ratio {
+        if max_dispute_loss_ratio > Decimal::one() {
+            return Err(ContractError::InvalidTakerRequirements {
+                message: "The max dispute loss ratio can not be greater than 1.".to_string(),
+            });
+        }
+    }
+    if taker_requirements.required_badges.len() > BADGES_LIMIT {
+        return Err(ContractError::InvalidTakerRequirements {
+            message: format!("It can not require more than {} badges.", BADGES_LIMIT),
+        });
+    }
+    for badge in taker_requirements.required_badges.iter() {
+        assert_badge_name_valid(badge)?;
+    }
+    if let Some(address_filter) = &taker_requirements.address_filter {
+        if address_filter.addresses().len() > OFFER_ADDRESS_FILTER_LIMIT {
+            return Err(ContractError::InvalidTakerRequirements {
+                message: format!(
+                    "The address filter can not have more than {} addresses.",
+                    OFFER_ADDRESS_FILTER_LIMIT
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn assert_taker_requirements(
+    taker_requirements: &Option<TakerRequirements>,
+    taker_profile: &Profile,
+    now: u64,
+) -> Result<(), ContractError> {
+    let taker_requirements = match taker_requirements {
+        Some(taker_requirements) => taker_requirements,
+        None => return Ok(()),
+    };
+    if let Some(address_filter) = &taker_requirements.address_filter {
+        if !address_filter.is_allowed(&taker_profile.addr) {
+            return Err(ContractError::TakerAddressNotAllowed {
+                taker: taker_profile.addr.clone(),
+            });
+        }
+    }
+    if let Some(min_released_trades) = taker_requirements.min_released_trades {
+        if taker_profile.released_trades_count < min_released_trades {
+            return Err(ContractError::TakerReleasedTradesTooLow {
+                min_released_trades,
+                released_trades_count: taker_profile.released_trades_count,
+            });
+        }
+    }
+    if let Some(min_profile_age) = taker_requirements.min_profile_age {
+        let profile_age = if taker_profile.created_at.eq(&0) {
+            0
+        } else {
+            now.saturating_sub(taker_profile.created_at)
+        };
+        if profile_age < min_profile_age {
+            return Err(ContractError::TakerProfileTooNew {
+                min_profile_age,
+                profile_age,
+            });
+        }
+    }
+    if let Some(max_dispute_loss_ratio) = taker_requirements.max_dispute_loss_ratio {
+        let dispute_loss_ratio = taker_profile.dispute_loss_ratio();
+        if dispute_loss_ratio > max_dispute_loss_ratio {
+            return Err(ContractError::TakerDisputeLossRatioTooHigh {
+                max_dispute_loss_ratio,
+                dispute_loss_ratio,
+            });
+        }
+    }
+    if let Some(badge) = taker_requirements
+        .required_badges
+        .iter()
+        .find(|badge| !taker_profile.has_active_badge(badge, now))
+    {
+        return Err(ContractError::TakerBadgeMissing {
+            badge: badge.clone(),
+        });
+    }
+    Ok(())
+}
+
+// Badge names are lowercase slugs, e.g. "kyc-level-1"
+pub fn assert_badge_name_valid(badge: &str) -> Result<(), ContractError> {
+    let is_valid = !badge.is_empty()
+        && badge.len() <= BADGE_NAME_LIMIT
+        && badge
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(ContractError::InvalidParameter {
+            parameter: "badge".to_string(),
+            message: Some(format!(
+                "It must have up to {} lowercase letters, digits or dashes.",
+                BADGE_NAME_LIMIT
+            )),
+        })
+    }
+}
+
+// The commitment is a sha256 hash of the attested data, hex encoded
+pub fn assert_commitment_valid(commitment: &str) -> Result<(), ContractError> {
+    if commitment.len() == 64 && commitment.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(ContractError::InvalidParameter {
+            parameter: "commitment".to_string(),
+            message: Some("It must be a hex encoded sha256 hash.".to_string()),
+        })
+    }
+}
+
+pub fn assert_review_valid(review: &Review) -> Result<(), ContractError> {
+    if review.rating < MIN_RATING || review.rating > MAX_RATING {
+        return Err(ContractError::ValueOutOfRange {
+            value: review.rating as usize,
+            range_start: MIN_RATING as usize,
+            range_end: MAX_RATING as usize,
+        });
+    }
+    let comment = review.comment.clone().unwrap_or_default();
+    if comment.len() > REVIEW_COMMENT_LIMIT {
+        return Err(ContractError::InvalidParameter {
+            parameter: "comment".to_string(),
+            message: Some(format!(
+                "The comment can not be longer than {} characters.",
+                REVIEW_COMMENT_LIMIT
+            )),
+        });
+    }
+    Ok(())
+}
+
+pub fn assert_suspension_valid(
+    reason: &str,
+    expires_at: Option<u64>,
+    now: u64,
+) -> Result<(), ContractError> {
+    if reason.is_empty() || reason.len() > SUSPENSION_REASON_LIMIT {
+        return Err(ContractError::InvalidParameter {
+            parameter: "reason".to_string(),
+            message: Some(format!(
+                "The reason must have between 1 and {} characters.",
+                SUSPENSION_REASON_LIMIT
+            )),
+        });
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ContractError::InvalidParameter {
+            parameter: "expires_at".to_string(),
+            message: Some("The suspension must expire in the future.".to_string()),
+        });
+    }
+    Ok(())
+}
+
+pub fn assert_profile_not_suspended(profile: &Profile, now: u64) -> Result<(), ContractError> {
+    match profile.active_suspension(now) {
+        Some(suspension) => Err(ContractError::ProfileSuspended {
+            profile_addr: profile.addr.clone(),
+            reason: suspension.reason.clone(),
+            expires_at: suspension.expires_at,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contacts_must_be_base64() {
+        assert!(assert_contact_valid("aGVsbG8=").is_ok());
+        assert!(assert_contact_valid("aGk=").is_ok());
+        assert!(assert_contact_valid("aA==").is_ok());
+        assert!(assert_contact_valid("a+/9").is_ok());
+
+        assert!(assert_contact_valid("").is_err());
+        assert!(assert_contact_valid("aGVsbG8").is_err());
+        assert!(assert_contact_valid("a===").is_err());
+        assert!(assert_contact_valid("aG$k").is_err());
+        assert!(assert_contact_valid(&"a".repeat(CONTACT_LIMIT + 4)).is_err());
+    }
+
+    #[test]
+    fn routes_must_chain_to_the_target() {
+        let kuji = Denom::Native("ukuji".to_string());
+        let axl_usdc = Denom::Native("ibc/axlusdc".to_string());
+        let usk = Denom::Native("factory/usk".to_string());
+
+        assert!(assert_route_chains(&kuji, &[(&kuji, &axl_usdc), (&axl_usdc, &usk)], &usk).is_ok());
+        // The second hop doesn't swap what the first one returned
+        assert!(assert_route_chains(&kuji, &[(&kuji, &axl_usdc), (&kuji, &usk)], &usk).is_err());
+        // The route doesn't start at the denom
+        assert!(assert_route_chains(&kuji, &[(&axl_usdc, &usk)], &usk).is_err());
+        // The route doesn't end in the target
+        assert!(assert_route_chains(&kuji, &[(&kuji, &axl_usdc)], &usk).is_err());
+        assert!(assert_route_chains(&kuji, &[], &usk).is_err());
+    }
+}
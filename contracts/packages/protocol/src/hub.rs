@@ -1,7 +1,11 @@
+use crate::currencies::FiatCurrency;
+use crate::denom_utils::denom_to_string;
+use crate::profile::Profile;
 use cosmwasm_std::{Addr, Decimal};
 use cw20::Denom;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 ///Messages
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -13,14 +17,51 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig(HubConfig),
-    UpdateAdmin { admin_addr: Addr },
+    ExecuteConfigUpdate {},
+    CancelConfigUpdate {},
+    UpdateAdmin {
+        admin_addr: Addr,
+    },
+    AcceptAdmin {},
+    UpdatePause {
+        target: PauseTarget,
+        paused: bool,
+    },
+    UpdateReputationTiers {
+        tiers: Vec<ReputationTier>,
+    },
+    UpdateModerator {
+        moderator: Option<Addr>,
+    },
+    UpdateAttester {
+        attester: Addr,
+        badges: Option<Vec<String>>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    VersionedConfig {},
+    ConfigAt {
+        version: Option<u64>,
+        height: Option<u64>,
+    },
     Admin {},
+    PendingConfig {},
+    PendingAdmin {},
+    PauseStatus {},
+    TradeLimits {
+        fiat: FiatCurrency,
+        denom: Denom,
+    },
+    ReputationTiers {},
+    Moderator {},
+    Attester {
+        addr: Addr,
+    },
+    Attesters {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -28,6 +69,19 @@ pub struct Admin {
     pub addr: Addr,
 }
 
+/// An address allowed to attest the given badges on profiles.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Attester {
+    pub addr: Addr,
+    pub badges: Vec<String>,
+}
+
+impl Attester {
+    pub fn can_attest(&self, badge: &str) -> bool {
+        self.badges.iter().any(|b| b.eq(badge))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct HubConfig {
     pub offer_addr: Addr,
@@ -49,8 +103,343 @@ pub struct HubConfig {
     pub trade_dispute_timer: u64,
     pub trade_limit_min: u128, // in USD
     pub trade_limit_max: u128, // in USD
+    // Configs stored before the timelock existed are applied right away
+    #[serde(default)]
+    pub config_timelock: u64, // in seconds
+    #[serde(default)]
+    pub trade_price_twap_window: u64, // in seconds, trades are priced on spot when 0
+    // Overrides of the trade limits, they go through the timelock like the rest of the config
+    #[serde(default)]
+    pub fiat_trade_limits: Vec<(FiatCurrency, TradeLimits)>,
+    #[serde(default)]
+    pub denom_trade_limits: Vec<(Denom, TradeLimits)>,
+}
+
+// Builds a list of (field, "old -> new") pairs for every field that differs between both configs.
+macro_rules! config_diff {
+    ($old:expr, $new:expr, $($field:ident),+) => {{
+        let mut diff: Vec<(String, String)> = vec![];
+        $(
+            if $old.$field != $new.$field {
+                diff.push((
+                    stringify!($field).to_string(),
+                    format!("{} -> {}", $old.$field, $new.$field),
+                ));
+            }
+        )+
+        diff
+    }};
+}
+
+impl HubConfig {
+    pub fn diff(&self, new: &HubConfig) -> Vec<(String, String)> {
+        let mut diff = config_diff!(
+            self,
+            new,
+            offer_addr,
+            trade_addr,
+            profile_addr,
+            price_addr,
+            price_provider_addr,
+            local_market_addr,
+            chain_fee_collector_addr,
+            warchest_addr,
+            active_offers_limit,
+            active_trades_limit,
+            arbitration_fee_pct,
+            burn_fee_pct,
+            chain_fee_pct,
+            warchest_fee_pct,
+            trade_expiration_timer,
+            trade_dispute_timer,
+            trade_limit_min,
+            trade_limit_max,
+            config_timelock,
+            trade_price_twap_window
+        );
+        if self.fiat_trade_limits != new.fiat_trade_limits {
+            diff.push((
+                "fiat_trade_limits".to_string(),
+                format!(
+                    "{} -> {}",
+                    trade_limits_to_string(&self.fiat_trade_limits, FiatCurrency::to_string),
+                    trade_limits_to_string(&new.fiat_trade_limits, FiatCurrency::to_string)
+                ),
+            ));
+        }
+        if self.denom_trade_limits != new.denom_trade_limits {
+            diff.push((
+                "denom_trade_limits".to_string(),
+                format!(
+                    "{} -> {}",
+                    trade_limits_to_string(&self.denom_trade_limits, denom_to_string),
+                    trade_limits_to_string(&new.denom_trade_limits, denom_to_string)
+                ),
+            ));
+        }
+        if self.local_denom != new.local_denom {
+            diff.push((
+                "local_denom".to_string(),
+                format!(
+                    "{} -> {}",
+                    denom_to_string(&self.local_denom),
+                    denom_to_string(&new.local_denom)
+                ),
+            ));
+        }
+        diff
+    }
+}
+
+fn trade_limits_to_string<K>(
+    overrides: &[(K, TradeLimits)],
+    key_to_string: fn(&K) -> String,
+) -> String {
+    overrides
+        .iter()
+        .map(|(key, limits)| format!("{}:{}-{}", key_to_string(key), limits.min, limits.max))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Trade limits in USD. Overrides can be set per fiat and per denom, falling back to the
+/// `trade_limit_min` and `trade_limit_max` of the HubConfig.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradeLimits {
+    pub min: u128,
+    pub max: u128,
+}
+
+impl TradeLimits {
+    // When both fiat and denom overrides exist, the most restrictive range applies
+    pub fn effective(config: &HubConfig, fiat: &FiatCurrency, denom: &Denom) -> TradeLimits {
+        let fiat_limits = config
+            .fiat_trade_limits
+            .iter()
+            .find(|(key, _)| key.eq(fiat))
+            .map(|(_, limits)| limits.clone());
+        let denom_limits = config
+            .denom_trade_limits
+            .iter()
+            .find(|(key, _)| key.eq(denom))
+            .map(|(_, limits)| limits.clone());
+        match (fiat_limits, denom_limits) {
+            (Some(fiat_limits), Some(denom_limits)) => TradeLimits {
+                min: fiat_limits.min.max(denom_limits.min),
+                max: fiat_limits.max.min(denom_limits.max),
+            },
+            (Some(limits), None) | (None, Some(limits)) => limits,
+            (None, None) => TradeLimits {
+                min: config.trade_limit_min,
+                max: config.trade_limit_max,
+            },
+        }
+    }
+}
+
+/// A profile reaching all the requirements of a tier may trade up to its `trade_limit_max` (in USD).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReputationTier {
+    pub min_released_trades: u64,
+    pub min_account_age: u64, // in seconds
+    pub max_disputes_lost: u64,
+    pub trade_limit_max: u128,
+}
+
+impl ReputationTier {
+    pub fn is_reached(&self, profile: &Profile, now: u64) -> bool {
+        let account_age = if profile.created_at.eq(&0) {
+            0
+        } else {
+            now.saturating_sub(profile.created_at)
+        };
+        profile.released_trades_count >= self.min_released_trades
+            && account_age >= self.min_account_age
+            && profile.disputes_lost_count <= self.max_disputes_lost
+    }
+
+    // Returns the highest limit among the reached tiers. A profile that doesn't reach any of them,
+    // or when no tiers are configured, is only bound by the trade limits of the HubConfig.
+    pub fn trade_limit_for(tiers: &[ReputationTier], profile: &Profile, now: u64) -> Option<u128> {
+        tiers
+            .iter()
+            .filter(|tier| tier.is_reached(profile, now))
+            .map(|tier| tier.trade_limit_max)
+            .max()
+    }
+}
+
+/// A config accepted by the Hub, along with the block in which it took effect.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionedConfig {
+    pub version: u64,
+    pub height: u64,
+    pub time: u64,
+    pub config: HubConfig,
+}
+
+/// The Hub state besides the config that the registered contracts keep a copy of.
+/// It's pushed on every change, with a `version` increasing on each push.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HubState {
+    pub version: u64,
+    pub admin: Admin,
+    pub pause_status: PauseStatus,
+    pub reputation_tiers: Vec<ReputationTier>,
+    pub moderator: Option<Addr>,
+    pub attesters: Vec<Attester>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingConfig {
+    pub config: HubConfig,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+}
+
+/// Contracts registered on the Hub that can be paused as a whole.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolContract {
+    Offer,
+    Trade,
+    Profile,
+    Price,
+}
+
+/// State mutating operations guarded by the pause registry.
+/// Release, refund and dispute paths are intentionally not listed, so user funds can always
+/// leave the escrow, even when the protocol is paused.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolOperation {
+    CreateOffer,
+    UpdateOffer,
+    CreateTrade,
+    AcceptRequest,
+    FundEscrow,
+    Swap,
+    UpdateProfile,
+    UpdatePrices,
+}
+
+impl ProtocolOperation {
+    pub fn contract(&self) -> ProtocolContract {
+        match self {
+            ProtocolOperation::CreateOffer | ProtocolOperation::UpdateOffer => {
+                ProtocolContract::Offer
+            }
+            ProtocolOperation::CreateTrade
+            | ProtocolOperation::AcceptRequest
+            | ProtocolOperation::FundEscrow
+            | ProtocolOperation::Swap => ProtocolContract::Trade,
+            ProtocolOperation::UpdateProfile => ProtocolContract::Profile,
+            ProtocolOperation::UpdatePrices => ProtocolContract::Price,
+        }
+    }
+}
+
+impl fmt::Display for ProtocolContract {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ProtocolOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseTarget {
+    Global,
+    Contract(ProtocolContract),
+    Operation(ProtocolOperation),
+}
+
+impl fmt::Display for PauseTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PauseTarget::Global => write!(f, "global"),
+            PauseTarget::Contract(contract) => write!(f, "contract:{}", contract),
+            PauseTarget::Operation(operation) => write!(f, "operation:{}", operation),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseStatus {
+    pub global: bool,
+    pub contracts: Vec<ProtocolContract>,
+    pub operations: Vec<ProtocolOperation>,
+}
+
+impl PauseStatus {
+    pub fn is_paused(&self, operation: &ProtocolOperation) -> bool {
+        self.global
+            || self.contracts.contains(&operation.contract())
+            || self.operations.contains(operation)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trade_limit_overrides_are_merged() {
+        let kuji = Denom::Native("ukuji".to_string());
+        let usk = Denom::Native("factory/usk".to_string());
+        let mut config = HubConfig {
+            offer_addr: Addr::unchecked("offer"),
+            trade_addr: Addr::unchecked("trade"),
+            profile_addr: Addr::unchecked("profile"),
+            price_addr: Addr::unchecked("price"),
+            price_provider_addr: Addr::unchecked("price_provider"),
+            local_market_addr: Addr::unchecked("local_market"),
+            local_denom: Denom::Native("factory/local".to_string()),
+            chain_fee_collector_addr: Addr::unchecked("chain_fee_collector"),
+            warchest_addr: Addr::unchecked("warchest"),
+            active_offers_limit: 4,
+            active_trades_limit: 20,
+            arbitration_fee_pct: Decimal::zero(),
+            burn_fee_pct: Decimal::zero(),
+            chain_fee_pct: Decimal::zero(),
+            warchest_fee_pct: Decimal::zero(),
+            trade_expiration_timer: 0,
+            trade_dispute_timer: 0,
+            trade_limit_min: 1,
+            trade_limit_max: 500,
+            config_timelock: 0,
+            trade_price_twap_window: 0,
+            fiat_trade_limits: vec![],
+            denom_trade_limits: vec![],
+        };
+        assert_eq!(
+            TradeLimits::effective(&config, &FiatCurrency::BRL, &kuji),
+            TradeLimits { min: 1, max: 500 }
+        );
+
+        config.fiat_trade_limits = vec![(FiatCurrency::BRL, TradeLimits { min: 10, max: 300 })];
+        config.denom_trade_limits = vec![(kuji.clone(), TradeLimits { min: 50, max: 1000 })];
+        assert_eq!(
+            TradeLimits::effective(&config, &FiatCurrency::BRL, &usk),
+            TradeLimits { min: 10, max: 300 }
+        );
+        assert_eq!(
+            TradeLimits::effective(&config, &FiatCurrency::ARS, &kuji),
+            TradeLimits { min: 50, max: 1000 }
+        );
+        // The most restrictive range of both applies
+        assert_eq!(
+            TradeLimits::effective(&config, &FiatCurrency::BRL, &kuji),
+            TradeLimits { min: 50, max: 300 }
+        );
+    }
+}
@@ -1,22 +1,47 @@
-use crate::hub::{Admin, HubConfig, QueryMsg};
+use crate::currencies::FiatCurrency;
+use crate::errors::ContractError;
+use crate::guards::assert_ownership;
+use crate::hub::{
+    Admin, Attester, HubConfig, HubState, PauseStatus, ProtocolOperation, QueryMsg, ReputationTier,
+    TradeLimits, VersionedConfig,
+};
 use cosmwasm_std::{
     to_binary, Addr, CustomQuery, Deps, QueryRequest, Response, Storage, WasmQuery,
 };
+use cw20::Denom;
 use cw_storage_plus::Item;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub fn get_hub_config<T: CustomQuery>(deps: Deps<T>) -> HubConfig {
+    get_versioned_hub_config(deps).config
+}
+
+// The Hub pushes every accepted config to the registered contracts, so it's only queried
+// until the first push is received.
+pub fn get_versioned_hub_config<T: CustomQuery>(deps: Deps<T>) -> VersionedConfig {
+    if let Some(hub_config) = HUB_CONFIG.may_load(deps.storage).unwrap() {
+        return hub_config;
+    }
     let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
     deps.querier
         .query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: hub_addr.addr.to_string(),
-            msg: to_binary(&QueryMsg::Config {}).unwrap(),
+            msg: to_binary(&QueryMsg::VersionedConfig {}).unwrap(),
         }))
         .unwrap()
 }
 
+// Like the config, the rest of the Hub state is pushed on every change,
+// the Hub is only queried until the first push is received.
+fn get_hub_state<T: CustomQuery>(deps: Deps<T>) -> Option<HubState> {
+    HUB_STATE.may_load(deps.storage).unwrap()
+}
+
 pub fn get_hub_admin<T: CustomQuery>(deps: Deps<T>) -> Admin {
+    if let Some(hub_state) = get_hub_state(deps) {
+        return hub_state.admin;
+    }
     let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
     deps.querier
         .query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -26,7 +51,85 @@ pub fn get_hub_admin<T: CustomQuery>(deps: Deps<T>) -> Admin {
         .unwrap()
 }
 
+pub fn get_pause_status<T: CustomQuery>(deps: Deps<T>) -> PauseStatus {
+    if let Some(hub_state) = get_hub_state(deps) {
+        return hub_state.pause_status;
+    }
+    let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
+    deps.querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_addr.addr.to_string(),
+            msg: to_binary(&QueryMsg::PauseStatus {}).unwrap(),
+        }))
+        .unwrap()
+}
+
+// The overrides are part of the config, so the limits don't need a query to the Hub
+pub fn get_trade_limits<T: CustomQuery>(
+    deps: Deps<T>,
+    fiat: FiatCurrency,
+    denom: Denom,
+) -> TradeLimits {
+    TradeLimits::effective(&get_hub_config(deps), &fiat, &denom)
+}
+
+pub fn get_reputation_tiers<T: CustomQuery>(deps: Deps<T>) -> Vec<ReputationTier> {
+    if let Some(hub_state) = get_hub_state(deps) {
+        return hub_state.reputation_tiers;
+    }
+    let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
+    deps.querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_addr.addr.to_string(),
+            msg: to_binary(&QueryMsg::ReputationTiers {}).unwrap(),
+        }))
+        .unwrap()
+}
+
+pub fn get_moderator<T: CustomQuery>(deps: Deps<T>) -> Option<Addr> {
+    if let Some(hub_state) = get_hub_state(deps) {
+        return hub_state.moderator;
+    }
+    let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
+    deps.querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_addr.addr.to_string(),
+            msg: to_binary(&QueryMsg::Moderator {}).unwrap(),
+        }))
+        .unwrap()
+}
+
+pub fn get_attester<T: CustomQuery>(deps: Deps<T>, addr: Addr) -> Option<Attester> {
+    if let Some(hub_state) = get_hub_state(deps) {
+        return hub_state
+            .attesters
+            .into_iter()
+            .find(|attester| attester.addr.eq(&addr));
+    }
+    let hub_addr = HUB_ADDR.load(deps.storage).unwrap();
+    deps.querier
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: hub_addr.addr.to_string(),
+            msg: to_binary(&QueryMsg::Attester { addr }).unwrap(),
+        }))
+        .unwrap()
+}
+
+// Should be called before mutating state on any pausable operation
+pub fn assert_not_paused<T: CustomQuery>(
+    deps: Deps<T>,
+    operation: ProtocolOperation,
+) -> Result<(), ContractError> {
+    if get_pause_status(deps).is_paused(&operation) {
+        Err(ContractError::OperationPaused { operation })
+    } else {
+        Ok(())
+    }
+}
+
 pub const HUB_ADDR: Item<HubAddr> = Item::new("HubConfig");
+pub const HUB_CONFIG: Item<VersionedConfig> = Item::new("hub_config_cache");
+pub const HUB_STATE: Item<HubState> = Item::new("hub_state_cache");
 
 pub fn register_hub_internal<T, E>(
     hub_addr: Addr,
@@ -56,6 +159,58 @@ pub fn register_hub_internal<T, E>(
     Ok(res)
 }
 
+pub fn update_hub_config_internal<T>(
+    sender: Addr,
+    store: &mut dyn Storage,
+    config: VersionedConfig,
+) -> Result<Response<T>, ContractError> {
+    let hub_addr = HUB_ADDR.load(store).unwrap();
+    assert_ownership(sender, hub_addr.addr)?;
+
+    // The hub pushes through a SubMsg, failing here would revert the hub's transaction,
+    // so a config that isn't newer than the cached one is ignored
+    if let Some(cached_config) = HUB_CONFIG.may_load(store).unwrap() {
+        if config.version <= cached_config.version {
+            return Ok(Response::new()
+                .add_attribute("action", "update_hub_config")
+                .add_attribute("ignored_config_version", config.version.to_string())
+                .add_attribute("config_version", cached_config.version.to_string()));
+        }
+    }
+    HUB_CONFIG.save(store, &config).unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "update_hub_config")
+        .add_attribute("config_version", config.version.to_string());
+    Ok(res)
+}
+
+pub fn update_hub_state_internal<T>(
+    sender: Addr,
+    store: &mut dyn Storage,
+    state: HubState,
+) -> Result<Response<T>, ContractError> {
+    let hub_addr = HUB_ADDR.load(store).unwrap();
+    assert_ownership(sender, hub_addr.addr)?;
+
+    // The hub pushes through a SubMsg, failing here would revert the hub's transaction,
+    // so a state that isn't newer than the cached one is ignored
+    if let Some(cached_state) = HUB_STATE.may_load(store).unwrap() {
+        if state.version <= cached_state.version {
+            return Ok(Response::new()
+                .add_attribute("action", "update_hub_state")
+                .add_attribute("ignored_state_version", state.version.to_string())
+                .add_attribute("state_version", cached_state.version.to_string()));
+        }
+    }
+    HUB_STATE.save(store, &state).unwrap();
+
+    let res = Response::new()
+        .add_attribute("action", "update_hub_state")
+        .add_attribute("state_version", state.version.to_string());
+    Ok(res)
+}
+
 ///Data
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct HubAddr {
@@ -1,10 +1,14 @@
 use crate::currencies::FiatCurrency;
 use crate::denom_utils::denom_to_string;
 use crate::guards::validate_min_max_items_per_page;
+use crate::hub::{HubState, VersionedConfig};
 use crate::hub_utils::get_hub_config;
-use crate::profile::{load_profile, load_profiles, Profile};
+use crate::profile::{load_blocked_users, load_profile, load_profiles_by_addrs, Profile};
 use crate::trade::{TradeResponse, TradeState};
-use cosmwasm_std::{Addr, CustomQuery, Deps, Order, QuerierWrapper, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, CustomQuery, Decimal, Deps, Order, QuerierWrapper, StdResult,
+    Storage, SubMsg, Uint128, WasmMsg,
+};
 use cw20::Denom;
 use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, MultiIndex};
 use schemars::JsonSchema;
@@ -54,14 +58,16 @@ pub struct InstantiateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct OfferMsg {
     pub offer_type: OfferType,
-    pub owner_contact: String,
-    pub owner_encryption_key: String,
+    pub owner_contact: Option<String>,
+    pub owner_encryption_key: Option<String>,
     pub fiat_currency: FiatCurrency,
     pub rate: Uint128,
     pub denom: Denom,
     pub min_amount: Uint128,
     pub max_amount: Uint128,
+    pub fiat_limits: Option<FiatLimits>,
     pub description: Option<String>,
+    pub taker_requirements: Option<TakerRequirements>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -72,8 +78,10 @@ pub struct OfferUpdateMsg {
     pub rate: Uint128,
     pub min_amount: Uint128,
     pub max_amount: Uint128,
+    pub fiat_limits: Option<FiatLimits>,
     pub state: OfferState,
     pub description: Option<String>,
+    pub taker_requirements: Option<TakerRequirements>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -82,7 +90,10 @@ pub enum ExecuteMsg {
     //TODO: Change to Create(OfferMsg)
     Create { offer: OfferMsg },
     UpdateOffer { offer_update: OfferUpdateMsg },
+    PauseOffersByOwner { owner: Addr },
     RegisterHub {},
+    UpdateHubConfig(Box<VersionedConfig>),
+    UpdateHubState(Box<HubState>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -99,6 +110,7 @@ pub enum QueryMsg {
         order: OfferOrder,
         limit: u32,
         last: Option<u64>,
+        viewer: Option<Addr>,
     },
     OffersByOwner {
         owner: Addr,
@@ -121,10 +133,52 @@ pub struct Offer {
     pub rate: Uint128,
     pub min_amount: Uint128,
     pub max_amount: Uint128,
+    pub fiat_limits: Option<FiatLimits>,
     pub description: Option<String>,
     pub denom: Denom,
     pub state: OfferState,
     pub timestamp: u64,
+    pub taker_requirements: Option<TakerRequirements>,
+}
+
+/// Limits of the offer in whole units of its `fiat_currency`. When set, they replace
+/// `min_amount` and `max_amount`, converted to the denom at the price of each trade.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FiatLimits {
+    pub min: Uint128,
+    pub max: Uint128,
+}
+
+/// Requirements set by the maker that the taker's profile must meet to open a trade.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TakerRequirements {
+    pub min_released_trades: Option<u64>,
+    pub min_profile_age: Option<u64>, // in seconds
+    pub max_dispute_loss_ratio: Option<Decimal>,
+    pub address_filter: Option<AddressFilter>,
+    pub required_badges: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFilter {
+    Allowlist(Vec<Addr>),
+    Blocklist(Vec<Addr>),
+}
+
+impl AddressFilter {
+    pub fn addresses(&self) -> &Vec<Addr> {
+        match self {
+            AddressFilter::Allowlist(addresses) | AddressFilter::Blocklist(addresses) => addresses,
+        }
+    }
+
+    pub fn is_allowed(&self, addr: &Addr) -> bool {
+        match self {
+            AddressFilter::Allowlist(addresses) => addresses.contains(addr),
+            AddressFilter::Blocklist(addresses) => !addresses.contains(addr),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -169,8 +223,10 @@ impl OfferModel<'_> {
         self.offer.rate = msg.rate;
         self.offer.min_amount = msg.min_amount;
         self.offer.max_amount = msg.max_amount;
+        self.offer.fiat_limits = msg.fiat_limits;
         self.offer.state = msg.state;
         self.offer.description = msg.description;
+        self.offer.taker_requirements = msg.taker_requirements;
         OfferModel::store(self.storage, &self.offer).unwrap();
         &self.offer
     }
@@ -215,6 +271,7 @@ impl OfferModel<'_> {
         order: OfferOrder,
         limit: u32,
         last: Option<u64>,
+        viewer: Option<Addr>,
     ) -> StdResult<Vec<OfferResponse>> {
         let hub_config = get_hub_config(deps);
         let storage = deps.storage;
@@ -222,48 +279,49 @@ impl OfferModel<'_> {
         let range_from = last.map(Bound::exclusive);
         let limit = validate_min_max_items_per_page(limit);
 
-        let mut profiles = load_profiles(
-            &deps.querier,
-            hub_config.profile_addr.to_string(),
-            limit,
-            None,
-        )
-        .unwrap();
-
         let prefix = fiat_currency.to_string()
             + &offer_type.to_string()
             + &denom_to_string(&denom)
             + &*OfferState::Active.to_string();
 
-        let mut result: Vec<OfferResponse> = offers()
+        // Offers from users blocked by the viewer are not listed
+        let blocked_users = match viewer {
+            Some(viewer) => {
+                load_blocked_users(&deps.querier, hub_config.profile_addr.to_string(), viewer)?
+            }
+            None => vec![],
+        };
+
+        let offers: Vec<Offer> = offers()
             .idx
             .filter
             .prefix(prefix)
             .range(storage, None, range_from, std_order)
-            .flat_map(|item| {
-                item.and_then(|(_, offer)| {
-                    let profile_found = profiles
-                        .clone()
-                        .into_iter()
-                        .find(|profile| profile.addr.eq(&offer.owner));
-
-                    let profile = if profile_found.is_some() {
-                        profile_found.unwrap()
-                    } else {
-                        let new_profile = load_profile(
-                            &deps.querier,
-                            hub_config.profile_addr.to_string(),
-                            offer.owner.clone(),
-                        )
-                        .unwrap();
-                        profiles.push(new_profile.clone());
-                        new_profile
-                    };
-
-                    Ok(OfferResponse { offer, profile })
-                })
+            .filter(|item| match item {
+                Ok((_, offer)) => !blocked_users.contains(&offer.owner),
+                Err(_) => true,
             })
             .take(limit as usize)
+            .flat_map(|item| item.map(|(_, offer)| offer))
+            .collect();
+
+        // Load the profiles of all the offer owners at once
+        let mut owners: Vec<Addr> = offers.iter().map(|offer| offer.owner.clone()).collect();
+        owners.sort();
+        owners.dedup();
+        let profiles =
+            load_profiles_by_addrs(&deps.querier, hub_config.profile_addr.to_string(), owners)?;
+
+        let mut result: Vec<OfferResponse> = offers
+            .into_iter()
+            .map(|offer| {
+                let profile = profiles
+                    .iter()
+                    .find(|profile| profile.addr.eq(&offer.owner))
+                    .cloned()
+                    .unwrap_or_else(|| Profile::new(offer.owner.clone(), 0));
+                OfferResponse { offer, profile }
+            })
             .collect();
 
         match order {
@@ -277,6 +335,27 @@ impl OfferModel<'_> {
             OfferOrder::PriceRate => {
                 result.sort_by(|prev, next| prev.offer.rate.cmp(&next.offer.rate));
             }
+            OfferOrder::Rating => {
+                result.sort_by(|prev, next| {
+                    next.profile
+                        .average_rating
+                        .cmp(&prev.profile.average_rating)
+                });
+            }
+            OfferOrder::Volume => {
+                result.sort_by(|prev, next| {
+                    next.profile
+                        .usd_traded_volume
+                        .cmp(&prev.profile.usd_traded_volume)
+                });
+            }
+            OfferOrder::DisputeLossRatio => {
+                result.sort_by(|prev, next| {
+                    prev.profile
+                        .dispute_loss_ratio()
+                        .cmp(&next.profile.dispute_loss_ratio())
+                });
+            }
         }
 
         Ok(result)
@@ -317,6 +396,9 @@ pub enum OfferType {
 pub enum OfferOrder {
     TradesCount,
     PriceRate,
+    Rating,
+    Volume,
+    DisputeLossRatio,
 }
 
 impl fmt::Display for OfferType {
@@ -341,6 +423,14 @@ pub enum OfferState {
 
 // Price
 
+pub fn pause_offers_by_owner_msg(offer_contract: String, owner: Addr) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: offer_contract,
+        msg: to_binary(&ExecuteMsg::PauseOffersByOwner { owner }).unwrap(),
+        funds: vec![],
+    }))
+}
+
 // Queries
 pub fn load_offer<T: CustomQuery>(
     querier: &QuerierWrapper<T>,
@@ -1,30 +1,156 @@
-use crate::{currencies::FiatCurrency, denom_utils::denom_to_string};
-use cosmwasm_std::{Addr, CustomQuery, QuerierWrapper, StdResult, Uint128, Uint256};
+use crate::{
+    currencies::FiatCurrency,
+    denom_utils::denom_to_string,
+    errors::ContractError,
+    hub::{HubState, VersionedConfig},
+};
+use cosmwasm_std::{
+    from_slice, to_vec, Addr, CustomQuery, Decimal, QuerierWrapper, StdError, StdResult, Uint128,
+    Uint256,
+};
 use cw20::Denom;
-use cw_storage_plus::Map;
+use cw_storage_plus::{Item, Map};
+use kujira::asset::{Asset, AssetInfo};
+use kujira::denom::Denom as KujiraDenom;
+use kujira::fin::{QueryMsg as FinQueryMsg, SimulationResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use std::fmt;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     RegisterHub {},
+    UpdateHubConfig(Box<VersionedConfig>),
+    UpdateHubState(Box<HubState>),
     RegisterPriceRouteForDenom {
         denom: Denom,
         route: Vec<PriceRoute>,
     },
+    RegisterDenomMetadata {
+        denom: Denom,
+        metadata: DenomMetadata,
+    },
+    UpdateBaseOracle(BaseOracle),
+    PushPrice {
+        symbol: String,
+        usd_price: Decimal,
+    },
     UpdatePrices(Vec<CurrencyPrice>),
+    UpdateFiatPriceMaxAge {
+        fiat: FiatCurrency,
+        max_age: Option<u64>,
+    },
+    UpdatePushedPriceMaxAge {
+        symbol: String,
+        max_age: Option<u64>,
+    },
+    UpdatePriceProvider {
+        provider: Addr,
+        enabled: bool,
+    },
+    UpdatePriceAggregation(PriceAggregation),
+    UpdateFiatMaxPriceChange {
+        fiat: FiatCurrency,
+        max_change: Option<Decimal>,
+    },
+    ConfirmPendingPrice {
+        fiat: FiatCurrency,
+    },
+    RejectPendingPrice {
+        fiat: FiatCurrency,
+    },
+    SampleDenomPrices {
+        denoms: Vec<Denom>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {
+    // Required when no base oracle is stored yet, routes must end in its denom
+    #[serde(default)]
+    pub base_oracle_denom: Option<Denom>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    Price { fiat: FiatCurrency, denom: Denom },
+    Price {
+        fiat: FiatCurrency,
+        denom: Denom,
+    },
+    Prices {
+        denom: Denom,
+        fiats: Option<Vec<FiatCurrency>>,
+    },
+    AllFiatPrices {
+        limit: u32,
+        last: Option<FiatCurrency>,
+    },
+    DenomMetadata {
+        denom: Denom,
+    },
+    BaseOracle {},
+    PushedPrice {
+        symbol: String,
+    },
+    FiatPriceMaxAge {
+        fiat: FiatCurrency,
+    },
+    PushedPriceMaxAge {
+        symbol: String,
+    },
+    PriceProviders {},
+    PriceAggregation {},
+    PriceSubmissions {
+        fiat: FiatCurrency,
+    },
+    FiatMaxPriceChange {
+        fiat: FiatCurrency,
+    },
+    PendingPrice {
+        fiat: FiatCurrency,
+    },
+    PriceAt {
+        fiat: FiatCurrency,
+        denom: Denom,
+        time: u64,
+    },
+    Twap {
+        fiat: FiatCurrency,
+        denom: Denom,
+        window: u64,
+    },
 }
 
 pub const FIAT_PRICE: Map<&str, CurrencyPrice> = Map::new("fiat_price");
+// Routes registered before denoms had metadata, they are read as denoms with the default decimals
 pub const DENOM_PRICE_ROUTE: Map<&str, Vec<PriceRoute>> = Map::new("denom_price_route");
+pub const DENOM_METADATA: Map<&str, DenomMetadata> = Map::new("denom_metadata");
+pub const BASE_ORACLE: Item<BaseOracle> = Item::new("base_oracle");
+// USD prices pushed by the admin for the AdminPushed sources, by symbol
+pub const PUSHED_PRICES: Map<&str, PushedPrice> = Map::new("pushed_prices");
+// Max seconds since the last update before a fiat price is considered stale
+pub const FIAT_PRICE_MAX_AGE: Map<&str, u64> = Map::new("fiat_price_max_age");
+// Max seconds since the last push before a pushed price is considered stale, by symbol
+pub const PUSHED_PRICE_MAX_AGE: Map<&str, u64> = Map::new("pushed_price_max_age");
+// Registered price providers, with the time they were registered
+pub const PRICE_PROVIDERS: Map<&Addr, u64> = Map::new("price_providers");
+// Last price submitted by each provider, by (fiat, provider)
+pub const PRICE_SUBMISSIONS: Map<(&str, &Addr), CurrencyPrice> = Map::new("price_submissions");
+pub const PRICE_AGGREGATION: Item<PriceAggregation> = Item::new("price_aggregation");
+// Max relative change of a fiat price in a single update, bigger changes are held as pending
+pub const FIAT_MAX_PRICE_CHANGE: Map<&str, Decimal> = Map::new("fiat_max_price_change");
+// A fiat with a pending price is halted until it's confirmed or rejected
+pub const PENDING_FIAT_PRICE: Map<&str, PendingPrice> = Map::new("pending_fiat_price");
+// Ring buffers of the last PRICE_HISTORY_LIMIT prices, by (fiat or denom, slot)
+pub const FIAT_PRICE_HISTORY: Map<(&str, u32), CurrencyPrice> = Map::new("fiat_price_history");
+pub const DENOM_PRICE_HISTORY: Map<(&str, u32), DenomPriceSample> = Map::new("denom_price_history");
+// Number of samples ever pushed to each ring buffer, the next slot is this count modulo the limit
+pub const FIAT_PRICE_HISTORY_COUNT: Map<&str, u32> = Map::new("fiat_price_history_count");
+pub const DENOM_PRICE_HISTORY_COUNT: Map<&str, u32> = Map::new("denom_price_history_count");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -42,33 +168,420 @@ impl CurrencyPrice {
             updated_at: 0u64,
         }
     }
+
+    pub fn is_stale(&self, now: u64, max_age: u64) -> bool {
+        now.saturating_sub(self.updated_at) > max_age
+    }
+}
+
+/// How the submissions of the price providers are combined into the fiat price.
+/// The price is the median of the fresh submissions that are within `max_deviation`
+/// of the median of all of them, and it's only updated once `quorum` of them are left.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceAggregation {
+    pub quorum: u32,
+    pub max_deviation: Option<Decimal>,
+    pub max_submission_age: u64, // in seconds
+}
+
+impl Default for PriceAggregation {
+    // A single fresh submission sets the price, as before providers were introduced
+    fn default() -> Self {
+        PriceAggregation {
+            quorum: 1,
+            max_deviation: None,
+            max_submission_age: 0,
+        }
+    }
+}
+
+/// Price of the denom in base oracle units (ATOM by default), in 10^12 units per
+/// whole denom. It's converted to USD with the base oracle when the samples are read.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomPriceSample {
+    pub base_price: Uint256,
+    pub timestamp: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPrice {
+    pub price: CurrencyPrice,
+    pub previous_usd_price: Uint128,
+    pub proposed_by: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceSubmission {
+    pub provider: Addr,
+    pub price: CurrencyPrice,
+}
+
+/// `price` is the fiat price of one whole unit of the denom, in cents.
+/// Amounts of the denom in base units must be divided by 10^`decimals`.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DenomFiatPrice {
     pub denom: Denom,
     pub fiat: FiatCurrency,
     pub price: Uint256,
+    pub decimals: u8,
+}
+
+/// A `DenomFiatPrice` along with the time its fiat price was last updated.
+/// When the fiat price can't be used, `error` tells why and the price is zero.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomFiatPriceResponse {
+    pub denom: Denom,
+    pub fiat: FiatCurrency,
+    pub price: Uint256,
+    pub decimals: u8,
+    pub updated_at: u64,
+    pub error: Option<PriceQueryError>,
+}
+
+/// The asset the routes of every denom end in, priced in USD by `source`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BaseOracle {
+    pub source: PriceSource,
+    pub denom: Option<Denom>,
+    pub decimals: u8,
+}
+
+/// A denom is either priced in USD by the single source of its `route`,
+/// or its `route` swaps it to the base oracle asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomMetadata {
+    pub decimals: u8,
+    pub route: Vec<PriceSource>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PushedPrice {
+    pub usd_price: Decimal,
+    pub updated_at: u64,
+}
+
+impl PushedPrice {
+    pub fn is_stale(&self, now: u64, max_age: u64) -> bool {
+        now.saturating_sub(self.updated_at) > max_age
+    }
+}
+
+/// Where a price comes from. `Fin` and `CwPair` are swap hops between two assets,
+/// the other sources give the USD price of a whole unit of an asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Fin {
+        pool: Addr,
+        offer_asset: Denom,
+        ask_asset: Denom,
+    },
+    CwPair {
+        pool: Addr,
+        offer_asset: Denom,
+        ask_asset: Denom,
+    },
+    Fixed {
+        usd_price: Decimal,
+    },
+    AdminPushed {
+        symbol: String,
+    },
+    KujiraOracle {
+        symbol: String,
+    },
+}
+
+impl PriceSource {
+    /// The `(offer_asset, ask_asset)` of swap hops, `None` for USD sources.
+    pub fn hop(&self) -> Option<(&Denom, &Denom)> {
+        match self {
+            PriceSource::Fin {
+                offer_asset,
+                ask_asset,
+                ..
+            }
+            | PriceSource::CwPair {
+                offer_asset,
+                ask_asset,
+                ..
+            } => Some((offer_asset, ask_asset)),
+            _ => None,
+        }
+    }
+}
+
+impl From<PriceRoute> for PriceSource {
+    fn from(route: PriceRoute) -> Self {
+        PriceSource::Fin {
+            pool: route.pool,
+            offer_asset: route.offer_asset,
+            ask_asset: route.ask_asset,
+        }
+    }
+}
+
+impl fmt::Display for PriceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceSource::Fin {
+                pool,
+                offer_asset,
+                ask_asset,
+            } => write!(
+                f,
+                "fin pool: {}, offer_asset: {}, ask_asset: {}",
+                pool,
+                denom_to_string(offer_asset),
+                denom_to_string(ask_asset)
+            ),
+            PriceSource::CwPair {
+                pool,
+                offer_asset,
+                ask_asset,
+            } => write!(
+                f,
+                "cw_pair pool: {}, offer_asset: {}, ask_asset: {}",
+                pool,
+                denom_to_string(offer_asset),
+                denom_to_string(ask_asset)
+            ),
+            PriceSource::Fixed { usd_price } => write!(f, "fixed usd_price: {}", usd_price),
+            PriceSource::AdminPushed { symbol } => write!(f, "admin_pushed symbol: {}", symbol),
+            PriceSource::KujiraOracle { symbol } => write!(f, "kujira_oracle symbol: {}", symbol),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceRoute {
     pub pool: Addr,
     pub offer_asset: Denom,
+    // Routes stored before the ask asset existed read it empty, see `DENOM_PRICE_ROUTE`
+    #[serde(default)]
+    pub ask_asset: Denom,
 }
 
 impl fmt::Display for PriceRoute {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let denom_str = denom_to_string(&self.offer_asset);
         write!(
             f,
-            "pool: {}, offer_asset: {}",
-            self.pool.to_string(),
-            denom_str
+            "pool: {}, offer_asset: {}, ask_asset: {}",
+            self.pool,
+            denom_to_string(&self.offer_asset),
+            denom_to_string(&self.ask_asset)
         )
     }
 }
 
+// Value in whole fiat units of an amount in base units of a denom, `price` being in cents
+pub fn fiat_value(amount: Uint128, price: Uint256, decimals: u8) -> Uint256 {
+    (Uint256::from(amount) * price)
+        .checked_div(Uint256::from_u128(100u128))
+        .unwrap_or(Uint256::zero())
+        .checked_div(Uint256::from_u128(10u128.pow(decimals as u32)))
+        .unwrap_or(Uint256::zero())
+}
+
+// Amount in base units of a denom worth `fiat_amount` whole fiat units, `price` being in cents
+pub fn denom_amount(fiat_amount: Uint128, price: Uint256, decimals: u8) -> Uint128 {
+    let amount = (Uint256::from(fiat_amount) * Uint256::from_u128(100u128))
+        * Uint256::from_u128(10u128.pow(decimals as u32));
+    amount
+        .checked_div(price)
+        .ok()
+        .and_then(|amount| Uint128::try_from(amount).ok())
+        .unwrap_or(Uint128::MAX)
+}
+
+// Amount of the ask asset the FIN pool returns for `amount` of `offer_asset`
+pub fn simulate_swap<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    pool: &Addr,
+    offer_asset: &Denom,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let simulation: SimulationResponse = querier.query_wasm_smart(
+        pool.to_string(),
+        &FinQueryMsg::Simulation {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: KujiraDenom::from(denom_to_string(offer_asset)),
+                },
+                amount,
+            },
+        },
+    )?;
+    Uint128::try_from(simulation.return_amount).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+// Queries can only fail with a StdError, the typed price errors are kept in its message
+// as a JSON payload after this prefix
+pub const PRICE_QUERY_ERROR_PREFIX: &str = "price_query_error: ";
+
+/// The price errors a query can carry back to the contract querying the price.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceQueryError {
+    PriceRouteNotFound {
+        denom: String,
+    },
+    FiatPriceNotFound {
+        fiat: FiatCurrency,
+    },
+    OracleUnavailable {
+        symbol: String,
+        message: String,
+    },
+    PoolSimulationFailed {
+        pool: String,
+        message: String,
+    },
+    StaleFiatPrice {
+        fiat: FiatCurrency,
+        updated_at: u64,
+        max_age: u64,
+    },
+    StaleDenomPrice {
+        denom: String,
+        updated_at: u64,
+        max_age: u64,
+    },
+    FiatPriceHalted {
+        fiat: FiatCurrency,
+    },
+    StalePushedPrice {
+        symbol: String,
+        updated_at: u64,
+        max_age: u64,
+    },
+}
+
+impl From<PriceQueryError> for ContractError {
+    fn from(error: PriceQueryError) -> Self {
+        match error {
+            PriceQueryError::PriceRouteNotFound { denom } => {
+                ContractError::PriceRouteNotFound { denom }
+            }
+            PriceQueryError::FiatPriceNotFound { fiat } => {
+                ContractError::FiatPriceNotFound { fiat }
+            }
+            PriceQueryError::OracleUnavailable { symbol, message } => {
+                ContractError::OracleUnavailable { symbol, message }
+            }
+            PriceQueryError::PoolSimulationFailed { pool, message } => {
+                ContractError::PoolSimulationFailed { pool, message }
+            }
+            PriceQueryError::StaleFiatPrice {
+                fiat,
+                updated_at,
+                max_age,
+            } => ContractError::StaleFiatPrice {
+                fiat,
+                updated_at,
+                max_age,
+            },
+            PriceQueryError::StaleDenomPrice {
+                denom,
+                updated_at,
+                max_age,
+            } => ContractError::StaleDenomPrice {
+                denom,
+                updated_at,
+                max_age,
+            },
+            PriceQueryError::FiatPriceHalted { fiat } => ContractError::FiatPriceHalted { fiat },
+            PriceQueryError::StalePushedPrice {
+                symbol,
+                updated_at,
+                max_age,
+            } => ContractError::StalePushedPrice {
+                symbol,
+                updated_at,
+                max_age,
+            },
+        }
+    }
+}
+
+pub fn price_query_error(error: ContractError) -> StdError {
+    let payload = match error {
+        ContractError::PriceRouteNotFound { denom } => {
+            PriceQueryError::PriceRouteNotFound { denom }
+        }
+        ContractError::FiatPriceNotFound { fiat } => PriceQueryError::FiatPriceNotFound { fiat },
+        ContractError::OracleUnavailable { symbol, message } => {
+            PriceQueryError::OracleUnavailable { symbol, message }
+        }
+        ContractError::PoolSimulationFailed { pool, message } => {
+            PriceQueryError::PoolSimulationFailed { pool, message }
+        }
+        ContractError::StaleFiatPrice {
+            fiat,
+            updated_at,
+            max_age,
+        } => PriceQueryError::StaleFiatPrice {
+            fiat,
+            updated_at,
+            max_age,
+        },
+        ContractError::StaleDenomPrice {
+            denom,
+            updated_at,
+            max_age,
+        } => PriceQueryError::StaleDenomPrice {
+            denom,
+            updated_at,
+            max_age,
+        },
+        ContractError::FiatPriceHalted { fiat } => PriceQueryError::FiatPriceHalted { fiat },
+        ContractError::StalePushedPrice {
+            symbol,
+            updated_at,
+            max_age,
+        } => PriceQueryError::StalePushedPrice {
+            symbol,
+            updated_at,
+            max_age,
+        },
+        error => return StdError::generic_err(error.to_string()),
+    };
+    match to_vec(&payload) {
+        Ok(json) => StdError::generic_err(format!(
+            "{}{}",
+            PRICE_QUERY_ERROR_PREFIX,
+            String::from_utf8_lossy(&json)
+        )),
+        Err(e) => e,
+    }
+}
+
+/// Recovers the typed price error from a failed price query,
+/// any other failure is reported as `PriceUnavailable`.
+pub fn price_error_from_query(
+    denom: &Denom,
+    fiat: &FiatCurrency,
+    error: StdError,
+) -> ContractError {
+    match price_query_error_payload(&error) {
+        Some(payload) => payload.into(),
+        None => ContractError::PriceUnavailable {
+            denom: denom_to_string(denom),
+            fiat: fiat.clone(),
+            message: error.to_string(),
+        },
+    }
+}
+
+/// The typed price error carried by a StdError, if any.
+pub fn price_query_error_payload(error: &StdError) -> Option<PriceQueryError> {
+    // The querier only wraps the message, so the first prefix is the outermost payload
+    error
+        .to_string()
+        .split_once(PRICE_QUERY_ERROR_PREFIX)
+        .and_then(|(_, json)| from_slice::<PriceQueryError>(json.as_bytes()).ok())
+}
+
 pub fn query_fiat_price_for_denom<T: CustomQuery>(
     querier: &QuerierWrapper<T>,
     denom: Denom,
@@ -77,3 +590,118 @@ pub fn query_fiat_price_for_denom<T: CustomQuery>(
 ) -> StdResult<DenomFiatPrice> {
     querier.query_wasm_smart(price_contract, &QueryMsg::Price { fiat, denom })
 }
+
+pub fn query_denom_metadata<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    denom: Denom,
+    price_contract: String,
+) -> StdResult<Option<DenomMetadata>> {
+    querier.query_wasm_smart(price_contract, &QueryMsg::DenomMetadata { denom })
+}
+
+pub fn query_twap_price_for_denom<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    denom: Denom,
+    fiat: FiatCurrency,
+    window: u64,
+    price_contract: String,
+) -> StdResult<DenomFiatPrice> {
+    querier.query_wasm_smart(
+        price_contract,
+        &QueryMsg::Twap {
+            fiat,
+            denom,
+            window,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_query_errors_round_trip() {
+        let denom = Denom::Native("ukuji".to_string());
+        let fiat = FiatCurrency::BRL;
+        let nested = price_query_error(ContractError::OracleUnavailable {
+            symbol: "USDC".to_string(),
+            message: "No exchange rate.".to_string(),
+        });
+        let errors = vec![
+            ContractError::PriceRouteNotFound {
+                denom: "ukuji".to_string(),
+            },
+            ContractError::FiatPriceNotFound { fiat: fiat.clone() },
+            ContractError::OracleUnavailable {
+                symbol: "KUJI".to_string(),
+                message: "No exchange rate.".to_string(),
+            },
+            ContractError::PoolSimulationFailed {
+                pool: "kujira1pool".to_string(),
+                message: nested.to_string(),
+            },
+            ContractError::StaleFiatPrice {
+                fiat: fiat.clone(),
+                updated_at: 100,
+                max_age: 60,
+            },
+            ContractError::StaleDenomPrice {
+                denom: "ukuji".to_string(),
+                updated_at: 100,
+                max_age: 3600,
+            },
+            ContractError::FiatPriceHalted { fiat: fiat.clone() },
+            ContractError::StalePushedPrice {
+                symbol: "KUJI".to_string(),
+                updated_at: 100,
+                max_age: 600,
+            },
+        ];
+        for error in errors {
+            let expected = error.to_string();
+            // The querier wraps the error of the price contract
+            let query_error = StdError::generic_err(format!(
+                "Querier contract error: {}",
+                price_query_error(error)
+            ));
+            let recovered = price_error_from_query(&denom, &fiat, query_error);
+            assert_eq!(recovered.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn other_query_errors_are_price_unavailable() {
+        let denom = Denom::Native("ukuji".to_string());
+        let recovered = price_error_from_query(
+            &denom,
+            &FiatCurrency::BRL,
+            StdError::generic_err("Something else."),
+        );
+        assert!(matches!(recovered, ContractError::PriceUnavailable { .. }));
+    }
+
+    #[test]
+    fn fiat_value_and_denom_amount_convert_both_ways() {
+        // 5.00 per whole unit of a denom with 6 decimals
+        let price = Uint256::from_u128(500);
+        assert_eq!(
+            fiat_value(Uint128::new(2_000_000), price, 6),
+            Uint256::from_u128(10)
+        );
+        assert_eq!(
+            denom_amount(Uint128::new(10), price, 6),
+            Uint128::new(2_000_000)
+        );
+        // Fractions of a fiat unit are dropped
+        assert_eq!(
+            fiat_value(Uint128::new(1_999_999), price, 6),
+            Uint256::from_u128(9)
+        );
+        // Without a price there is no amount that can be afforded
+        assert_eq!(
+            denom_amount(Uint128::new(10), Uint256::zero(), 6),
+            Uint128::MAX
+        );
+    }
+}
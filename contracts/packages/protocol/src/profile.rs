@@ -1,11 +1,19 @@
-use crate::{offer::OfferState, trade::TradeState};
+use crate::constants::MAX_ITEMS_PER_PAGE;
+use crate::guards::validate_min_max_items_per_page;
+use crate::{
+    hub::{HubState, VersionedConfig},
+    offer::OfferState,
+    trade::TradeState,
+};
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, CustomQuery, Deps, Env, Order, QuerierWrapper, StdResult, Storage,
-    SubMsg, WasmMsg,
+    to_binary, Addr, CosmosMsg, CustomQuery, Decimal, Deps, Order, QuerierWrapper, StdError,
+    StdResult, Storage, SubMsg, Uint128, Uint256, WasmMsg,
 };
-use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex};
+use cw20::Denom;
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Map, MultiIndex};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 // Messages
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -16,8 +24,8 @@ pub struct InstantiateMsg {}
 pub enum ExecuteMsg {
     UpdateContact {
         profile_addr: Addr,
-        contact: String,
-        encryption_key: String,
+        contact: Option<String>,
+        encryption_key: Option<String>,
     },
     UpdateActiveOffers {
         profile_addr: Addr,
@@ -27,14 +35,87 @@ pub enum ExecuteMsg {
         profile_addr: Addr,
         trade_state: TradeState,
     },
+    UpdateDisputesCount {
+        profile_addr: Addr,
+        dispute_event: DisputeEvent,
+    },
+    AddReview {
+        profile_addr: Addr,
+        review: Review,
+    },
+    RotateEncryptionKey {
+        encryption_key: String,
+    },
+    BlockUser {
+        addr: Addr,
+    },
+    UnblockUser {
+        addr: Addr,
+    },
+    SuspendProfile {
+        profile_addr: Addr,
+        reason: String,
+        expires_at: Option<u64>,
+    },
+    UnsuspendProfile {
+        profile_addr: Addr,
+    },
+    Attest {
+        profile_addr: Addr,
+        badge: String,
+        expires_at: u64,
+        commitment: Option<String>,
+    },
+    RevokeAttestation {
+        profile_addr: Addr,
+        badge: String,
+    },
+    UpdateTradedVolume {
+        profile_addr: Addr,
+        denom: Denom,
+        amount: Uint128,
+        usd_amount: Uint256,
+    },
+    // Saves the profiles again so the indexes added after they were stored include them
+    ReindexProfiles {
+        start_after: Option<Addr>,
+        limit: u32,
+    },
     RegisterHub {},
+    UpdateHubConfig(Box<VersionedConfig>),
+    UpdateHubState(Box<HubState>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    Profile { addr: Addr },
-    Profiles { limit: u32, start_at: Option<u64> },
+    Profile {
+        addr: Addr,
+    },
+    Profiles {
+        order: ProfileOrder,
+        limit: u32,
+        start_after: Option<Addr>,
+    },
+    ProfilesByAddrs {
+        addrs: Vec<Addr>,
+    },
+    EncryptionKey {
+        addr: Addr,
+        version: Option<u64>,
+    },
+    BlockedUsers {
+        addr: Addr,
+    },
+    IsBlockedBetween {
+        addr: Addr,
+        counterparty: Addr,
+    },
+    Reviews {
+        addr: Addr,
+        limit: u32,
+        last: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -45,8 +126,8 @@ pub struct MigrateMsg {}
 pub fn update_profile_contact_msg(
     profile_contract: String,
     profile_addr: Addr,
-    contact: String,
-    encryption_key: String,
+    contact: Option<String>,
+    encryption_key: Option<String>,
 ) -> SubMsg {
     SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: profile_contract,
@@ -76,6 +157,54 @@ pub fn update_profile_trades_count_msg(
     }))
 }
 
+pub fn update_profile_disputes_count_msg(
+    contract_addr: String,
+    profile_addr: Addr,
+    dispute_event: DisputeEvent,
+) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr,
+        msg: to_binary(&ExecuteMsg::UpdateDisputesCount {
+            profile_addr,
+            dispute_event,
+        })
+        .unwrap(),
+        funds: vec![],
+    }))
+}
+
+pub fn add_profile_review_msg(contract_addr: String, profile_addr: Addr, review: Review) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr,
+        msg: to_binary(&ExecuteMsg::AddReview {
+            profile_addr,
+            review,
+        })
+        .unwrap(),
+        funds: vec![],
+    }))
+}
+
+pub fn update_profile_traded_volume_msg(
+    contract_addr: String,
+    profile_addr: Addr,
+    denom: Denom,
+    amount: Uint128,
+    usd_amount: Uint256,
+) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr,
+        msg: to_binary(&ExecuteMsg::UpdateTradedVolume {
+            profile_addr,
+            denom,
+            amount,
+            usd_amount,
+        })
+        .unwrap(),
+        funds: vec![],
+    }))
+}
+
 pub fn update_profile_active_offers_msg(
     contract_addr: String,
     profile_addr: Addr,
@@ -109,10 +238,56 @@ pub fn load_profile<T: CustomQuery>(
 pub fn load_profiles<T: CustomQuery>(
     querier: &QuerierWrapper<T>,
     profile_contract: String,
+    order: ProfileOrder,
     limit: u32,
-    start_at: Option<u64>,
+    start_after: Option<Addr>,
 ) -> StdResult<Vec<Profile>> {
-    querier.query_wasm_smart(profile_contract, &QueryMsg::Profiles { limit, start_at })
+    querier.query_wasm_smart(
+        profile_contract,
+        &QueryMsg::Profiles {
+            order,
+            limit,
+            start_after,
+        },
+    )
+}
+
+// Loads the encryption key of a profile at a given version, or the latest one
+pub fn load_encryption_key<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    profile_contract: String,
+    addr: Addr,
+    version: Option<u64>,
+) -> StdResult<Option<String>> {
+    querier.query_wasm_smart(profile_contract, &QueryMsg::EncryptionKey { addr, version })
+}
+
+pub fn load_blocked_users<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    profile_contract: String,
+    addr: Addr,
+) -> StdResult<Vec<Addr>> {
+    querier.query_wasm_smart(profile_contract, &QueryMsg::BlockedUsers { addr })
+}
+
+pub fn is_blocked_between<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    profile_contract: String,
+    addr: Addr,
+    counterparty: Addr,
+) -> StdResult<bool> {
+    querier.query_wasm_smart(
+        profile_contract,
+        &QueryMsg::IsBlockedBetween { addr, counterparty },
+    )
+}
+
+pub fn load_profiles_by_addrs<T: CustomQuery>(
+    querier: &QuerierWrapper<T>,
+    profile_contract: String,
+    addrs: Vec<Addr>,
+) -> StdResult<Vec<Profile>> {
+    querier.query_wasm_smart(profile_contract, &QueryMsg::ProfilesByAddrs { addrs })
 }
 
 // Data
@@ -126,7 +301,35 @@ pub struct Profile {
     pub last_trade: u64,
     pub contact: Option<String>,
     pub encryption_key: Option<String>,
+    // The fields below were added after the first release, profiles stored before them
+    // are read with their default values
+    #[serde(default)]
+    pub encryption_key_version: u64,
     pub active_offers_count: u8,
+    #[serde(default)]
+    pub canceled_trades_count: u64,
+    #[serde(default)]
+    pub expired_trades_count: u64,
+    #[serde(default)]
+    pub disputes_opened_count: u64,
+    #[serde(default)]
+    pub disputes_won_count: u64,
+    #[serde(default)]
+    pub disputes_lost_count: u64,
+    #[serde(default)]
+    pub traded_volume: Vec<TradedVolume>,
+    #[serde(default)]
+    pub usd_traded_volume: Uint256,
+    #[serde(default)]
+    pub reviews_count: u64,
+    #[serde(default)]
+    pub rating_sum: u64,
+    #[serde(default)]
+    pub average_rating: Decimal,
+    #[serde(default)]
+    pub suspension: Option<Suspension>,
+    #[serde(default)]
+    pub badges: Vec<Badge>,
 }
 
 impl Profile {
@@ -139,12 +342,162 @@ impl Profile {
             last_trade: 0,
             contact: None,
             encryption_key: None,
+            encryption_key_version: 0,
             active_offers_count: 0,
             active_trades_count: 0,
+            canceled_trades_count: 0,
+            expired_trades_count: 0,
+            disputes_opened_count: 0,
+            disputes_won_count: 0,
+            disputes_lost_count: 0,
+            traded_volume: vec![],
+            usd_traded_volume: Uint256::zero(),
+            reviews_count: 0,
+            rating_sum: 0,
+            average_rating: Decimal::zero(),
+            suspension: None,
+            badges: vec![],
+        }
+    }
+
+    // Share of the finished trades (released or disputed) that ended with a lost dispute
+    pub fn dispute_loss_ratio(&self) -> Decimal {
+        let finished_trades_count =
+            self.released_trades_count + self.disputes_won_count + self.disputes_lost_count;
+        if finished_trades_count == 0 {
+            return Decimal::zero();
+        }
+        Decimal::from_ratio(self.disputes_lost_count, finished_trades_count)
+    }
+
+    pub fn add_traded_volume(&mut self, denom: Denom, amount: Uint128, usd_amount: Uint256) {
+        match self
+            .traded_volume
+            .iter_mut()
+            .find(|traded_volume| traded_volume.denom.eq(&denom))
+        {
+            Some(traded_volume) => traded_volume.amount += amount,
+            None => self.traded_volume.push(TradedVolume { denom, amount }),
         }
+        self.usd_traded_volume += usd_amount;
+    }
+
+    // The version the given key has, or will have once it's set on the profile
+    pub fn encryption_key_version_for(&self, encryption_key: &str) -> u64 {
+        if self.encryption_key.as_deref() == Some(encryption_key) {
+            self.encryption_key_version
+        } else {
+            self.encryption_key_version + 1
+        }
+    }
+
+    pub fn active_suspension(&self, now: u64) -> Option<&Suspension> {
+        self.suspension
+            .as_ref()
+            .filter(|suspension| suspension.is_active(now))
+    }
+
+    pub fn has_active_badge(&self, name: &str, now: u64) -> bool {
+        self.badges
+            .iter()
+            .any(|badge| badge.name.eq(name) && badge.is_active(now))
+    }
+
+    // Expired badges are kept in storage but hidden from queries
+    pub fn retain_active_badges(&mut self, now: u64) {
+        self.badges.retain(|badge| badge.is_active(now));
+    }
+
+    pub fn add_rating(&mut self, rating: u8) {
+        self.reviews_count += 1;
+        self.rating_sum += rating as u64;
+        self.average_rating = Decimal::from_ratio(self.rating_sum, self.reviews_count);
+    }
+}
+
+/// Set by the moderator. A suspension without `expires_at` lasts until it's lifted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Suspension {
+    pub reason: String,
+    pub moderator: Addr,
+    pub suspended_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+impl Suspension {
+    pub fn is_active(&self, now: u64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now < expires_at,
+            None => true,
+        }
+    }
+}
+
+/// Attested by a hub registered attester, e.g. "kyc-level-1". No personal data is kept on chain,
+/// only an optional `commitment` hash of the verified data held by the attester.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Badge {
+    pub name: String,
+    pub attester: Addr,
+    pub commitment: Option<String>,
+    pub attested_at: u64,
+    pub expires_at: u64,
+}
+
+impl Badge {
+    pub fn is_active(&self, now: u64) -> bool {
+        now < self.expires_at
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileOrder {
+    ReleasedTrades,
+    LastTrade,
+    CreatedAt,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradedVolume {
+    pub denom: Denom,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeEvent {
+    Opened,
+    Won,
+    Lost,
+}
+
+impl fmt::Display for DisputeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A review left by the counterparty of a finished trade.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Review {
+    pub trade_id: u64,
+    pub reviewer: Addr,
+    pub rating: u8,
+    pub comment: Option<String>,
+    pub created_at: u64,
+}
+
+// Reviews received by a profile, by trade id. A profile is reviewed at most once per trade.
+pub const REVIEWS: Map<(&Addr, u64), Review> = Map::new("reviews");
+
+// Every encryption key set on a profile, by (profile, version), so messages encrypted
+// with a previous key can still be decrypted.
+pub const ENCRYPTION_KEYS: Map<(&Addr, u64), String> = Map::new("encryption_keys");
+
+// Users blocked by each profile, by (blocker, blocked), with the time they were blocked.
+pub const BLOCKED_USERS: Map<(&Addr, &Addr), u64> = Map::new("blocked_users");
+
 // Model
 pub struct ProfileModel<'a> {
     pub profile: Profile,
@@ -172,30 +525,103 @@ impl ProfileModel<'_> {
         }
     }
 
-    pub fn query_profile(storage: &dyn Storage, profile_addr: Addr) -> Profile {
-        profiles()
-            .load(storage, profile_addr.to_string())
-            .unwrap_or(Profile::new(profile_addr, 0))
+    // Addresses without a profile get a new one, but a profile that can't be read is an error
+    pub fn query_profile(storage: &dyn Storage, profile_addr: Addr) -> StdResult<Profile> {
+        Ok(profiles()
+            .may_load(storage, profile_addr.to_string())?
+            .unwrap_or_else(|| Profile::new(profile_addr, 0)))
     }
 
     pub fn save<'a>(self) -> Profile {
         ProfileModel::store(self.storage, &self.profile).profile
     }
 
+    // Profiles are sorted in descending order, `start_after` is the last profile of the previous page.
     pub fn query_profiles(
         deps: Deps,
-        _env: Env,
+        order: ProfileOrder,
         limit: u32,
-        _start_at: Option<u64>,
+        start_after: Option<Addr>,
     ) -> StdResult<Vec<Profile>> {
-        let result = profiles()
-            .idx
-            .trades_count
-            .range(deps.storage, None, None, Order::Descending)
+        let limit = validate_min_max_items_per_page(limit) as usize;
+        let cursor = match start_after {
+            Some(addr) => Some(profiles().load(deps.storage, addr.to_string())?),
+            None => None,
+        };
+        let profiles = profiles();
+        let index = match order {
+            ProfileOrder::ReleasedTrades => &profiles.idx.trades_count,
+            ProfileOrder::LastTrade => &profiles.idx.last_trade,
+            ProfileOrder::CreatedAt => &profiles.idx.created_at,
+        };
+        let range_to = cursor.map(|profile| {
+            let index_key = match order {
+                ProfileOrder::ReleasedTrades => profile.released_trades_count,
+                ProfileOrder::LastTrade => profile.last_trade,
+                ProfileOrder::CreatedAt => profile.created_at,
+            };
+            Bound::exclusive((index_key, profile.addr.to_string()))
+        });
+        index
+            .range(deps.storage, None, range_to, Order::Descending)
+            .take(limit)
+            .map(|item| item.map(|(_, profile)| profile))
+            .collect()
+    }
+
+    pub fn query_profiles_by_addrs(deps: Deps, addrs: Vec<Addr>) -> StdResult<Vec<Profile>> {
+        if addrs.len() > MAX_ITEMS_PER_PAGE as usize {
+            return Err(StdError::generic_err(format!(
+                "Can not query more than {} profiles at once.",
+                MAX_ITEMS_PER_PAGE
+            )));
+        }
+        addrs
+            .into_iter()
+            .map(|addr| ProfileModel::query_profile(deps.storage, addr))
+            .collect()
+    }
+
+    pub fn query_encryption_key(
+        deps: Deps,
+        addr: Addr,
+        version: Option<u64>,
+    ) -> StdResult<Option<String>> {
+        let profile = ProfileModel::query_profile(deps.storage, addr.clone())?;
+        match version {
+            Some(version) if version.ne(&profile.encryption_key_version) => {
+                ENCRYPTION_KEYS.may_load(deps.storage, (&addr, version))
+            }
+            _ => Ok(profile.encryption_key),
+        }
+    }
+
+    pub fn query_blocked_users(deps: Deps, addr: Addr) -> StdResult<Vec<Addr>> {
+        BLOCKED_USERS
+            .prefix(&addr)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect()
+    }
+
+    pub fn query_is_blocked_between(deps: Deps, addr: Addr, counterparty: Addr) -> StdResult<bool> {
+        Ok(BLOCKED_USERS.has(deps.storage, (&addr, &counterparty))
+            || BLOCKED_USERS.has(deps.storage, (&counterparty, &addr)))
+    }
+
+    pub fn query_reviews(
+        deps: Deps,
+        addr: Addr,
+        limit: u32,
+        last: Option<u64>,
+    ) -> StdResult<Vec<Review>> {
+        let range_to = last.map(Bound::exclusive);
+        let limit = validate_min_max_items_per_page(limit);
+        REVIEWS
+            .prefix(&addr)
+            .range(deps.storage, None, range_to, Order::Descending)
             .take(limit as usize)
-            .flat_map(|item| item.and_then(|(_, profile)| Ok(profile)))
-            .collect();
-        Ok(result)
+            .map(|item| item.map(|(_, review)| review))
+            .collect()
     }
 }
 
@@ -203,11 +629,17 @@ pub struct ProfileIndexes<'a> {
     pub address: MultiIndex<'a, String, Profile, String>,
     pub trades_count: MultiIndex<'a, u64, Profile, String>,
     pub last_trade: MultiIndex<'a, u64, Profile, String>,
+    pub created_at: MultiIndex<'a, u64, Profile, String>,
 }
 
 impl<'a> IndexList<Profile> for ProfileIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Profile>> + '_> {
-        let v: Vec<&dyn Index<Profile>> = vec![&self.address, &self.trades_count, &self.last_trade];
+        let v: Vec<&dyn Index<Profile>> = vec![
+            &self.address,
+            &self.trades_count,
+            &self.last_trade,
+            &self.created_at,
+        ];
         Box::new(v.into_iter())
     }
 }
@@ -230,6 +662,11 @@ pub fn profiles<'a>() -> IndexedMap<'a, String, Profile, ProfileIndexes<'a>> {
             PROFILES_PK,
             "profiles__last_trade",
         ),
+        created_at: MultiIndex::new(
+            |p: &Profile| p.created_at,
+            PROFILES_PK,
+            "profiles__created_at",
+        ),
     };
     IndexedMap::new(PROFILES_PK, indexes)
 }
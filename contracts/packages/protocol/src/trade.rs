@@ -12,13 +12,16 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::currencies::FiatCurrency;
+use crate::denom_utils::denom_to_string;
 use crate::guards::assert_range_0_to_99;
+use crate::hub::{HubState, VersionedConfig};
 use crate::offer::Arbitrator;
-use crate::profile::Profile;
 
 pub const DENOM_CONVERSION_ROUTE: Map<&str, Vec<ConversionRoute>> =
     Map::new("denom_conversion_route");
 pub const DENOM_CONVERSION_STEP: Item<ConversionStep> = Item::new("denom_conversion_step");
+// Burn amounts kept on the contract while swaps were paused, by trade denom
+pub const PENDING_BURNS: Map<&str, Uint128> = Map::new("pending_burns");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
@@ -66,10 +69,20 @@ pub enum ExecuteMsg {
         winner: Addr,
     },
     RegisterHub {},
+    UpdateHubConfig(Box<VersionedConfig>),
+    UpdateHubState(Box<HubState>),
     RegisterConversionRouteForDenom {
         denom: Denom,
         route: Vec<ConversionRoute>,
     },
+    RateTrade {
+        trade_id: u64,
+        rating: u8,
+        comment: Option<String>,
+    },
+    RetryPendingBurn {
+        denom: Denom,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -91,6 +104,9 @@ pub enum QueryMsg {
     ArbitratorsFiat {
         fiat: FiatCurrency,
     },
+    PendingBurn {
+        denom: Denom,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -127,6 +143,18 @@ pub struct ConversionRoute {
     pub offer_asset: Denom,
 }
 
+impl Display for ConversionRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pool: {}, offer_asset: {}, ask_asset: {}",
+            self.pool,
+            denom_to_string(&self.offer_asset),
+            denom_to_string(&self.ask_asset)
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ConversionStep {
@@ -150,9 +178,12 @@ pub enum TraderRole {
 pub struct NewTrade {
     pub offer_id: u64,
     pub amount: Uint128,
+    // In whole units of the offer's fiat, when set the amount is computed from it
+    pub fiat_amount: Option<Uint128>,
     pub taker: Addr,
-    pub profile_taker_contact: String,
-    pub profile_taker_encryption_key: String,
+    pub profile_taker_contact: Option<String>,
+    pub profile_taker_encryption_key: Option<String>,
+    // Encrypted to the maker's key, the profile contact can't stand in for it
     pub taker_contact: String,
 }
 
@@ -209,6 +240,18 @@ pub struct Trade {
     pub amount: Uint128,
     pub fiat: FiatCurrency,
     pub denom_fiat_price: Uint256,
+    #[serde(default)]
+    pub usd_amount: Uint256,
+    // Trades opened before configs were versioned have version 0
+    #[serde(default)]
+    pub config_version: u64,
+    // Trades opened before keys were versioned used the first key of each profile
+    #[serde(default)]
+    pub buyer_encryption_key_version: u64,
+    #[serde(default)]
+    pub seller_encryption_key_version: u64,
+    #[serde(default)]
+    pub arbitrator_encryption_key_version: u64,
     pub state_history: Vec<TradeStateItem>,
     state: TradeState,
 }
@@ -230,6 +273,11 @@ impl Trade {
         amount: Uint128,
         fiat: FiatCurrency,
         denom_fiat_price: Uint256,
+        usd_amount: Uint256,
+        config_version: u64,
+        buyer_encryption_key_version: u64,
+        seller_encryption_key_version: u64,
+        arbitrator_encryption_key_version: u64,
         state_history: Vec<TradeStateItem>,
     ) -> Trade {
         return Trade {
@@ -251,6 +299,11 @@ impl Trade {
             amount,
             fiat,
             denom_fiat_price,
+            usd_amount,
+            config_version,
+            buyer_encryption_key_version,
+            seller_encryption_key_version,
+            arbitrator_encryption_key_version,
             state_history,
             state: TradeState::RequestCreated,
         };
@@ -310,16 +363,19 @@ pub struct TradeResponse {
     pub amount: Uint128,
     pub fiat: FiatCurrency,
     pub denom_fiat_price: Uint256,
+    pub usd_amount: Uint256,
+    pub config_version: u64,
     pub state_history: Vec<TradeStateItem>,
     pub state: TradeState,
 }
 
 impl TradeResponse {
+    // The encryption keys are the ones in effect when the trade was created
     pub fn map(
         trade: Trade,
-        buyer_profile: Profile,
-        seller_profile: Profile,
-        arbitrator_profile: Profile,
+        buyer_encryption_key: Option<String>,
+        seller_encryption_key: Option<String>,
+        arbitrator_encryption_key: Option<String>,
         block_time: u64,
     ) -> TradeResponse {
         let trade_states = vec![
@@ -340,7 +396,7 @@ impl TradeResponse {
         };
 
         let arbitrator_encryption_key: Option<String> = if state.eq(&TradeState::FiatDeposited) {
-            arbitrator_profile.encryption_key.clone()
+            arbitrator_encryption_key
         } else {
             None
         };
@@ -350,10 +406,10 @@ impl TradeResponse {
             addr: trade.addr,
             buyer: trade.buyer,
             buyer_contact: trade.buyer_contact,
-            buyer_encryption_key: buyer_profile.encryption_key,
+            buyer_encryption_key,
             seller: trade.seller,
             seller_contact: trade.seller_contact,
-            seller_encryption_key: seller_profile.encryption_key,
+            seller_encryption_key,
             arbitrator: arbitrator_address,
             arbitrator_encryption_key,
             arbitrator_seller_contact: trade.arbitrator_seller_contact,
@@ -367,6 +423,8 @@ impl TradeResponse {
             amount: trade.amount,
             fiat: trade.fiat,
             denom_fiat_price: trade.denom_fiat_price,
+            usd_amount: trade.usd_amount,
+            config_version: trade.config_version,
             state_history: trade.state_history,
             state,
         }